@@ -0,0 +1,206 @@
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use automata::{Automata, AutomataParams, AutomataTrait, GridSize};
+use automata::boundary::{Boundary, Neighbor};
+use automata::cell::NextFn;
+use automata::cell::prelude::*;
+use automata::neighborhood::{Neighborhood, Shape};
+use automata::rng::Rng;
+
+const WARMUP: u32 = 2;
+const GENERATIONS: u32 = 20;
+const THREADS: usize = 4;
+const CHUNKS: usize = 32;
+
+/// A chunk of the grid sent to a worker, which sends back each next cell on
+/// its own through `result_send`.
+struct ChannelItem<C> {
+    current: Arc<Box<[C]>>,
+    range: Range<usize>,
+    seed: u64,
+    result_send: Sender<(usize, C)>,
+}
+
+/// The engine before chunks were written in place: long-lived workers that
+/// receive a chunk over a channel and send one message per cell back to the
+/// main thread, which copies each cell into the next generation.
+struct ChannelEngine<C: Cell> {
+    current: Arc<Box<[C]>>,
+    next: Box<[C]>,
+    chunks: Box<[Range<usize>]>,
+    item_senders: Box<[Sender<ChannelItem<C>>]>,
+    rng: Rng,
+}
+
+impl<C> ChannelEngine<C>
+where C: 'static + Cell + Send + Sync + Clone + Default + PartialEq,
+      C::Params: 'static + Send + Sync
+{
+    fn new(params: AutomataParams, cell_params: C::Params) -> ChannelEngine<C> {
+        let AutomataParams { grid_size, threads, chunks, boundary, neighborhood, seed } = params;
+
+        let mut rng = Rng::new(seed);
+        let current = C::initial(&cell_params, grid_size, &mut rng);
+        let total = current.len();
+
+        let chunk_size = total / chunks;
+        let mut chunks_vec: Vec<Range<usize>> = (0..chunks - 1)
+            .map(|n| chunk_size * n..chunk_size * (n + 1))
+            .collect();
+        chunks_vec.push((chunk_size * (chunks - 1))..total);
+
+        // the old engine only calculated cells from their neighbors
+        let next = match C::update_mode(&cell_params) {
+            UpdateMode::Neighbors(next) => next,
+            _ => panic!("the channel engine only calculates cells from their neighbors"),
+        };
+
+        let cell_params = Arc::new(cell_params);
+        let offsets: Arc<[(isize, isize)]> = Arc::from(neighborhood.offsets());
+        let item_senders = (0..threads)
+            .map(|_| {
+                let cell_params = Arc::clone(&cell_params);
+                let offsets = Arc::clone(&offsets);
+                let (item_send, item_recv) = channel();
+                thread::spawn(move || {
+                    ChannelEngine::calculate_chunk(grid_size, boundary, offsets, cell_params, next, item_recv);
+                });
+
+                item_send
+            })
+            .collect();
+
+        ChannelEngine {
+            current: Arc::new(current.into_boxed_slice()),
+            next: vec![C::default(); total].into_boxed_slice(),
+            chunks: chunks_vec.into_boxed_slice(),
+            item_senders,
+            rng,
+        }
+    }
+
+    fn calculate_chunk(
+        grid_size: GridSize,
+        boundary: Boundary,
+        offsets: Arc<[(isize, isize)]>,
+        cell_params: Arc<C::Params>,
+        next: NextFn<C>,
+        item_recv: Receiver<ChannelItem<C>>,
+    ) {
+        let GridSize { cols, rows } = grid_size;
+        let fixed = C::default();
+
+        while let Ok(ChannelItem { current, range, seed, result_send }) = item_recv.recv() {
+            let mut neighbors: Vec<Option<&C>> = vec![None; offsets.len()];
+            for index in range {
+                let (col, row) = (index / rows, index % rows);
+                for ((dcol, drow), nopt) in offsets.iter().zip(neighbors.iter_mut()) {
+                    *nopt = match boundary.resolve(cols, rows, col as isize + dcol, row as isize + drow) {
+                        Neighbor::Index(nindex) => Some(&current[nindex]),
+                        Neighbor::Fixed => Some(&fixed),
+                        Neighbor::Outside => None,
+                    };
+                }
+
+                let mut rng = Rng::for_cell(seed, index);
+                let cell = next(&current[index], &cell_params, Neighbors::new(&offsets, &neighbors), &mut rng);
+
+                // SAFETY: the receiver is only dropped after every result is in
+                result_send.send((index, cell)).unwrap();
+            }
+        }
+    }
+
+    /// Calculates the next generation, returning how many cells changed.
+    fn next(&mut self) -> usize {
+        let seed = self.rng.next_u64();
+        let (result_send, result_recv) = channel();
+        for (range, sender) in self.chunks.iter().zip(self.item_senders.iter().cycle()) {
+            let item = ChannelItem {
+                current: Arc::clone(&self.current),
+                range: range.clone(),
+                seed,
+                result_send: Sender::clone(&result_send),
+            };
+
+            // SAFETY: workers live as long as the engine
+            sender.send(item).unwrap();
+        }
+
+        drop(result_send);
+
+        let mut changed = 0;
+        while let Ok((index, cell)) = result_recv.recv() {
+            if cell != self.current[index] {
+                changed += 1;
+            }
+
+            self.next[index] = cell;
+        }
+
+        // SAFETY: every worker has dropped its reference once all the results
+        // are back
+        let current = Arc::get_mut(&mut self.current).unwrap();
+        std::mem::swap(current, &mut self.next);
+        changed
+    }
+}
+
+fn time(mut next: impl FnMut()) -> Duration {
+    for _ in 0..WARMUP {
+        next();
+    }
+
+    let start = Instant::now();
+    for _ in 0..GENERATIONS {
+        next();
+    }
+
+    start.elapsed() / GENERATIONS
+}
+
+/// Compares the engine's chunked `next`, where threads write the next
+/// generation in place, with the channel-per-cell engine it replaced, on
+/// Life grids the size of a 1080p screen at a few different cell sizes.
+fn main() {
+    for cell_size in [1, 2, 5] {
+        let grid_size = GridSize {
+            cols: 1920 / cell_size,
+            rows: 1080 / cell_size,
+        };
+
+        let params = || AutomataParams {
+            grid_size,
+            threads: THREADS,
+            chunks: CHUNKS,
+            boundary: Boundary::Dead,
+            neighborhood: Neighborhood::Shape(Shape::Moore, 1),
+            seed: 0,
+        };
+
+        let life_params = || LifeParams {
+            alive_ratio: 0.5,
+            rule: "B3S23".parse().unwrap(),
+        };
+
+        let mut channel_engine = ChannelEngine::<Life>::new(params(), life_params());
+        let channel_per_gen = time(|| {
+            channel_engine.next();
+        });
+
+        let mut chunked = Automata::<Life>::new(params(), life_params());
+        let chunked_per_gen = time(|| chunked.next());
+
+        println!(
+            "life {}x{}: channel per cell {:.3} ms/generation, chunked {:.3} ms/generation ({:.1}x)",
+            grid_size.cols,
+            grid_size.rows,
+            channel_per_gen.as_secs_f64() * 1000.0,
+            chunked_per_gen.as_secs_f64() * 1000.0,
+            channel_per_gen.as_secs_f64() / chunked_per_gen.as_secs_f64(),
+        );
+    }
+}
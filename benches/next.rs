@@ -0,0 +1,50 @@
+use std::time::Instant;
+use automata::{Automata, AutomataParams, AutomataTrait, GridSize};
+use automata::boundary::Boundary;
+use automata::cell::prelude::*;
+use automata::neighborhood::{Neighborhood, Shape};
+
+const WARMUP: u32 = 2;
+const GENERATIONS: u32 = 20;
+
+/// Times how long the engine takes to calculate a generation of Life on
+/// grids the size of a 1080p screen at a few different cell sizes.
+fn main() {
+    for cell_size in [1, 2, 5] {
+        let grid_size = GridSize {
+            cols: 1920 / cell_size,
+            rows: 1080 / cell_size,
+        };
+
+        let params = AutomataParams {
+            grid_size,
+            threads: 4,
+            chunks: 32,
+            boundary: Boundary::Dead,
+            neighborhood: Neighborhood::Shape(Shape::Moore, 1),
+            seed: 0,
+        };
+
+        let mut automata = Automata::<Life>::new(params, LifeParams {
+            alive_ratio: 0.5,
+            rule: "B3S23".parse().unwrap(),
+        });
+
+        for _ in 0..WARMUP {
+            automata.next();
+        }
+
+        let start = Instant::now();
+        for _ in 0..GENERATIONS {
+            automata.next();
+        }
+
+        let per_gen = start.elapsed() / GENERATIONS;
+        println!(
+            "life {}x{}: {:.3} ms/generation",
+            grid_size.cols,
+            grid_size.rows,
+            per_gen.as_secs_f64() * 1000.0,
+        );
+    }
+}
@@ -0,0 +1,539 @@
+use std::str::FromStr;
+use crate::automata::GridSize;
+use crate::boundary::{Boundary, Neighbor};
+use crate::cell::Cell;
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// The color agents are drawn with, on top of the cells.
+pub const AGENT_COLOR: Color = RED;
+
+/// A turmite on the grid, facing north (0), east (1), south (2) or west (3).
+#[derive(Clone, Debug)]
+pub struct Agent {
+    pub col: usize,
+    pub row: usize,
+    pub direction: u8,
+    pub state: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+    None,
+    Right,
+    Around,
+    Left,
+}
+
+/// What a turmite does for one state and cell color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub write: u8,
+    pub turn: Turn,
+    pub state: u8,
+}
+
+/// The transition table of a turmite, indexed by state then cell color.
+///
+/// Langton's Ant style rules are written as one turn per color (e.g. "RL"
+/// or "LLRR"), and multi-state turmites in Golly's notation, e.g.
+/// "{{{1,2,0},{0,8,0}}}" where each transition is {color to write, turn,
+/// next state} and turns are 1 (none), 2 (right), 4 (around) or 8 (left).
+#[derive(Clone, Debug)]
+pub struct TurmiteRule {
+    table: Vec<Vec<Transition>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TurmiteRuleParseError {
+    pub message: &'static str,
+}
+
+/// The agents on the grid, which move between generations and change the
+/// state of the cells they leave.
+pub struct Agents {
+    rule: TurmiteRule,
+    agents: Vec<Agent>,
+    initial: Vec<Agent>,
+}
+
+impl Turn {
+    fn from_u8(value: u8) -> Option<Turn> {
+        match value {
+            1 => Some(Turn::None),
+            2 => Some(Turn::Right),
+            4 => Some(Turn::Around),
+            8 => Some(Turn::Left),
+            _ => None,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Turn::None => 1,
+            Turn::Right => 2,
+            Turn::Around => 4,
+            Turn::Left => 8,
+        }
+    }
+
+    /// The number of clockwise quarter turns.
+    fn quarters(self) -> u8 {
+        match self {
+            Turn::None => 0,
+            Turn::Right => 1,
+            Turn::Around => 2,
+            Turn::Left => 3,
+        }
+    }
+}
+
+impl TurmiteRule {
+    /// The number of cell colors the rule uses.
+    pub fn colors(&self) -> u8 {
+        self.table[0].len() as u8
+    }
+
+    fn from_turns(s: &str) -> Result<TurmiteRule, TurmiteRuleParseError> {
+        let colors = s.len();
+        let transitions = s.chars()
+            .enumerate()
+            .map(|(color, char)| {
+                let turn = match char {
+                    'L' => Turn::Left,
+                    'R' => Turn::Right,
+                    'N' => Turn::None,
+                    'U' => Turn::Around,
+                    _ => return Err(TurmiteRuleParseError {
+                        message: "ant rule can only include 'L', 'R', 'N' and 'U'",
+                    }),
+                };
+
+                Ok(Transition {
+                    write: ((color + 1) % colors) as u8,
+                    turn,
+                    state: 0,
+                })
+            })
+            .collect::<Result<Vec<Transition>, TurmiteRuleParseError>>()?;
+
+        Ok(TurmiteRule {
+            table: vec![transitions],
+        })
+    }
+
+    fn from_table(s: &str) -> Result<TurmiteRule, TurmiteRuleParseError> {
+        let format_err = TurmiteRuleParseError {
+            message: "turmite table must be of the form '{{{write,turn,state},...},...}'",
+        };
+
+        // collect the numbers in each {write,turn,state} group, by state
+        let mut states: Vec<Vec<Vec<u8>>> = Vec::new();
+        let (mut depth, mut number) = (0, String::new());
+        for char in s.chars().filter(|char| !char.is_whitespace()) {
+            match char {
+                '{' => {
+                    depth += 1;
+                    match depth {
+                        2 => states.push(Vec::new()),
+                        3 => states.last_mut().ok_or(format_err)?.push(Vec::new()),
+                        4.. => return Err(format_err),
+                        _ => {},
+                    }
+                },
+                '}' | ',' => {
+                    if !number.is_empty() {
+                        let value = number.parse().map_err(|_| TurmiteRuleParseError {
+                            message: "turmite table values must be from 0 through 255",
+                        })?;
+
+                        let group = states.last_mut()
+                            .and_then(|transitions| transitions.last_mut())
+                            .filter(|_| depth == 3)
+                            .ok_or(format_err)?;
+
+                        group.push(value);
+                        number.clear();
+                    }
+
+                    if char == '}' {
+                        depth -= 1;
+                        if depth < 0 {
+                            return Err(format_err);
+                        }
+                    }
+                },
+                '0'..='9' => number.push(char),
+                _ => return Err(format_err),
+            }
+        }
+
+        if depth != 0 || states.is_empty() {
+            return Err(format_err);
+        }
+
+        let colors = states[0].len();
+        let table = states.iter()
+            .map(|transitions| {
+                if transitions.len() != colors {
+                    return Err(TurmiteRuleParseError {
+                        message: "every turmite state must have a transition for each color",
+                    });
+                }
+
+                transitions.iter()
+                    .map(|group| match group[..] {
+                        [write, turn, state] if (write as usize) < colors && (state as usize) < states.len() => {
+                            let turn = Turn::from_u8(turn).ok_or(TurmiteRuleParseError {
+                                message: "turns must be 1 (none), 2 (right), 4 (around) or 8 (left)",
+                            })?;
+
+                            Ok(Transition { write, turn, state })
+                        },
+                        [_, _, _] => Err(TurmiteRuleParseError {
+                            message: "turmite table refers to a color or state it does not have",
+                        }),
+                        _ => Err(TurmiteRuleParseError {
+                            message: "turmite transitions must have a color, turn and state",
+                        }),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<Transition>>, TurmiteRuleParseError>>()?;
+
+        Ok(TurmiteRule { table })
+    }
+}
+
+impl FromStr for TurmiteRule {
+    type Err = TurmiteRuleParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rule = match s.trim().starts_with('{') {
+            true => TurmiteRule::from_table(s)?,
+            false => TurmiteRule::from_turns(&s.trim().to_ascii_uppercase())?,
+        };
+
+        if !(2..=255).contains(&rule.table[0].len()) {
+            return Err(TurmiteRuleParseError {
+                message: "turmite rules must have from 2 through 255 colors",
+            });
+        }
+
+        Ok(rule)
+    }
+}
+
+impl Agents {
+    /// Places `count` agents in state 0, the first in the center of the grid
+    /// facing north and the rest at random.
+    pub fn new(rule: TurmiteRule, count: usize, grid_size: GridSize, rng: &mut Rng) -> Agents {
+        let GridSize { cols, rows } = grid_size;
+        let agents: Vec<Agent> = (0..count)
+            .map(|n| match n {
+                0 => Agent {
+                    col: cols / 2,
+                    row: rows / 2,
+                    direction: 0,
+                    state: 0,
+                },
+                _ => Agent {
+                    col: rng.gen_below(cols),
+                    row: rng.gen_below(rows),
+                    direction: rng.gen_below(4) as u8,
+                    state: 0,
+                },
+            })
+            .collect();
+
+        Agents {
+            rule,
+            initial: agents.clone(),
+            agents,
+        }
+    }
+
+    pub fn agents(&self) -> &[Agent] {
+        &self.agents
+    }
+
+    /// The number of cell colors the agents' rule uses.
+    pub fn colors(&self) -> u8 {
+        self.rule.colors()
+    }
+
+    /// Moves every agent once, recording the indices of cells that changed.
+    /// Agents that would leave the grid turn around instead.
+    pub fn step<C: Cell + PartialEq>(&mut self, grid_size: GridSize, boundary: Boundary, params: &C::Params, cells: &mut [C], changed: &mut Vec<usize>) {
+        let GridSize { cols, rows } = grid_size;
+        for agent in self.agents.iter_mut() {
+            let index = agent.col * rows + agent.row;
+            let color = cells[index].state() as usize;
+            let Some(transition) = self.rule.table[agent.state as usize].get(color) else {
+                continue;
+            };
+
+            if let Some(cell) = C::from_state(transition.write, params) {
+                if cell.differs(&cells[index]) {
+                    cells[index] = cell;
+                    changed.push(index);
+                }
+            }
+
+            agent.direction = (agent.direction + transition.turn.quarters()) % 4;
+            agent.state = transition.state;
+
+            let (dcol, drow) = match agent.direction {
+                0 => (0, -1),
+                1 => (1, 0),
+                2 => (0, 1),
+                _ => (-1, 0),
+            };
+
+            let (ncol, nrow) = (agent.col as isize + dcol, agent.row as isize + drow);
+            match boundary.resolve(cols, rows, ncol, nrow) {
+                Neighbor::Index(nindex) => (agent.col, agent.row) = (nindex / rows, nindex % rows),
+                Neighbor::Fixed | Neighbor::Outside => agent.direction = (agent.direction + 2) % 4,
+            }
+        }
+    }
+
+    /// Moves the agents back to where they started.
+    pub fn reset(&mut self) {
+        self.agents = self.initial.clone();
+    }
+
+    pub fn write(&self, writer: &mut Writer) {
+        writer.u8(self.rule.table.len() as u8);
+        writer.u8(self.rule.colors());
+        for transition in self.rule.table.iter().flatten() {
+            writer.u8(transition.write);
+            writer.u8(transition.turn.to_u8());
+            writer.u8(transition.state);
+        }
+
+        for agents in [&self.agents, &self.initial] {
+            writer.u64(agents.len() as u64);
+            for agent in agents.iter() {
+                writer.u64(agent.col as u64);
+                writer.u64(agent.row as u64);
+                writer.u8(agent.direction);
+                writer.u8(agent.state);
+            }
+        }
+    }
+
+    /// Reads agents written by `write`, checking that the rule only refers to
+    /// its own colors and states, and that the agents are on the grid.
+    pub fn read(reader: &mut Reader, grid_size: GridSize) -> Option<Agents> {
+        let (states, colors) = (reader.u8()?, reader.u8()?);
+        if states == 0 || colors < 2 {
+            return None;
+        }
+
+        let table = (0..states)
+            .map(|_| {
+                (0..colors)
+                    .map(|_| {
+                        let transition = Transition {
+                            write: reader.u8()?,
+                            turn: Turn::from_u8(reader.u8()?)?,
+                            state: reader.u8()?,
+                        };
+
+                        let valid = transition.write < colors && transition.state < states;
+                        valid.then_some(transition)
+                    })
+                    .collect::<Option<Vec<Transition>>>()
+            })
+            .collect::<Option<Vec<Vec<Transition>>>>()?;
+
+        let mut read_agents = || {
+            let len = reader.u64()? as usize;
+            (0..len)
+                .map(|_| {
+                    let agent = Agent {
+                        col: reader.u64()? as usize,
+                        row: reader.u64()? as usize,
+                        direction: reader.u8()?,
+                        state: reader.u8()?,
+                    };
+
+                    let valid = agent.col < grid_size.cols && agent.row < grid_size.rows
+                        && agent.direction < 4 && agent.state < states;
+                    valid.then_some(agent)
+                })
+                .collect::<Option<Vec<Agent>>>()
+        };
+
+        let agents = read_agents()?;
+        let initial = read_agents()?;
+        Some(Agents {
+            rule: TurmiteRule { table },
+            agents,
+            initial,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::{Automata, AutomataParams, AutomataTrait};
+    use crate::automata::tests::{check_resume, params};
+    use crate::cell::prelude::*;
+    use crate::snapshot;
+
+    fn rule(s: &str) -> TurmiteRule {
+        s.parse().unwrap()
+    }
+
+    fn transition(write: u8, turn: Turn, state: u8) -> Transition {
+        Transition { write, turn, state }
+    }
+
+    #[test]
+    fn parses_ant_rules() {
+        assert_eq!(rule("rl").table, [[
+            transition(1, Turn::Right, 0),
+            transition(0, Turn::Left, 0),
+        ]]);
+
+        assert_eq!(rule("NUL").table, [[
+            transition(1, Turn::None, 0),
+            transition(2, Turn::Around, 0),
+            transition(0, Turn::Left, 0),
+        ]]);
+    }
+
+    #[test]
+    fn parses_golly_tables() {
+        assert_eq!(rule("{{{1,2,0},{0,8,0}}}").table, rule("RL").table);
+
+        // a two state turmite, with whitespace
+        let table = rule("{{{1, 2, 1}, {0, 8, 1}}, {{1, 1, 0}, {0, 4, 0}}}").table;
+        assert_eq!(table, [
+            [transition(1, Turn::Right, 1), transition(0, Turn::Left, 1)],
+            [transition(1, Turn::None, 0), transition(0, Turn::Around, 0)],
+        ]);
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        let bad = [
+            "",
+            "R",
+            "RLX",
+            "{{{1,2,0},{0,8,0}}",
+            "{{{1,2,0},{0,8,0}}}}",
+            "{{{{1,2,0}}}}",
+            "{1,2,0}",
+            "{{{1,2,0},{0,8,1}}}",
+            "{{{2,2,0},{0,8,0}}}",
+            "{{{1,3,0},{0,8,0}}}",
+            "{{{1,2},{0,8,0}}}",
+            "{{{1,2,0},{0,8,0}},{{1,2,0}}}",
+            "{{{1,2,0},{0,8,256}}}",
+            "{{{1,2,0},{0,8,-1}}}",
+        ];
+
+        for s in bad {
+            assert!(s.parse::<TurmiteRule>().is_err(), "{s:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn ants_turn_and_flip_cells() {
+        let grid_size = GridSize { cols: 5, rows: 5 };
+        let params = TurmiteParams::grayscale(2);
+        let mut cells = vec![Turmite::default(); 25];
+        let mut agents = Agents::new(rule("RL"), 1, grid_size, &mut Rng::new(1));
+
+        // the ant starts in the center facing north, turns right onto an
+        // empty cell, then left onto another
+        let mut changed = Vec::new();
+        agents.step(grid_size, Boundary::Dead, &params, &mut cells, &mut changed);
+        assert_eq!((agents.agents[0].col, agents.agents[0].row, agents.agents[0].direction), (3, 2, 1));
+        agents.step(grid_size, Boundary::Dead, &params, &mut cells, &mut changed);
+        assert_eq!((agents.agents[0].col, agents.agents[0].row, agents.agents[0].direction), (3, 3, 2));
+        assert_eq!(changed, [2 * 5 + 2, 3 * 5 + 2]);
+        assert!(changed.iter().all(|&index| cells[index].state() == 1));
+
+        // then turns around at the edge of a dead grid instead of leaving it
+        let mut agents = Agents::new(rule("NN"), 1, grid_size, &mut Rng::new(1));
+        for _ in 0..3 {
+            agents.step(grid_size, Boundary::Dead, &params, &mut cells, &mut changed);
+        }
+
+        assert_eq!((agents.agents[0].row, agents.agents[0].direction), (0, 2));
+        agents.step(grid_size, Boundary::Dead, &params, &mut cells, &mut changed);
+        assert_eq!((agents.agents[0].row, agents.agents[0].direction), (1, 2));
+    }
+
+    #[test]
+    fn rejects_snapshots_with_invalid_transitions() {
+        let grid_size = GridSize { cols: 5, rows: 5 };
+        let agents = Agents::new(rule("{{{1,2,1},{0,8,1}},{{1,1,0},{0,4,0}}}"), 2, grid_size, &mut Rng::new(1));
+        let mut writer = Writer::new();
+        agents.write(&mut writer);
+        let bytes = writer.into_bytes();
+        assert!(Agents::read(&mut Reader::new(&bytes), grid_size).is_some());
+
+        // a color, turn or state the rule does not have in any transition
+        for (offset, value) in [(2, 2), (3, 3), (4, 2), (11, 2), (13, 5)] {
+            let mut corrupt = bytes.clone();
+            corrupt[offset] = value;
+            assert!(Agents::read(&mut Reader::new(&corrupt), grid_size).is_none(), "{offset} = {value} should not load");
+        }
+
+        // no states, or too few colors
+        for header in [[0, 2], [2, 1]] {
+            let mut corrupt = bytes.clone();
+            corrupt[..2].copy_from_slice(&header);
+            assert!(Agents::read(&mut Reader::new(&corrupt), grid_size).is_none());
+        }
+    }
+
+    #[test]
+    fn only_agents_change_cells() {
+        let turmites = |boundary| {
+            let params = AutomataParams { boundary, ..params(30, 20) };
+            let grid_size = params.grid_size;
+            let agents = Agents::new(rule("LRRL"), 3, grid_size, &mut Rng::new(2));
+            Automata::<Turmite>::new(params, TurmiteParams::grayscale(4)).with_agents(agents)
+        };
+
+        // each ant changes at most the cell it leaves
+        let mut automata = turmites(Boundary::Torus);
+        for _ in 0..50 {
+            automata.next();
+            assert!(automata.changed() <= 3);
+        }
+
+        check_resume(turmites(Boundary::Torus), 50);
+        check_resume(turmites(Boundary::Dead), 50);
+    }
+
+    #[test]
+    fn rejects_snapshots_with_palettes_for_other_rules() {
+        let load = |colors: u8| {
+            let params = params(10, 10);
+            let agents = Agents::new(rule("LRRL"), 1, params.grid_size, &mut Rng::new(1));
+            let automata = Automata::<Turmite>::new(params, TurmiteParams::grayscale(colors)).with_agents(agents);
+            let bytes = automata.save_snapshot();
+            let (_, mut reader) = snapshot::read_header(&bytes).unwrap();
+            Automata::<Turmite>::from_snapshot(&mut reader, 1, 1).is_ok()
+        };
+
+        assert!(load(4));
+        assert!(!load(3));
+        assert!(!load(5));
+
+        // palettes without a color to paint
+        for colors in [0, 1] {
+            let mut writer = Writer::new();
+            Turmite::write_params(&TurmiteParams::grayscale(colors), &mut writer);
+            assert!(Turmite::read_params(&mut Reader::new(&writer.into_bytes())).is_none());
+        }
+    }
+}
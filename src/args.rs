@@ -1,12 +1,22 @@
 use std::str::FromStr;
 use clap::{Args, ValueEnum, Parser, Subcommand};
-use crate::cell::life::LifeRule;
+use automata::boundary::Boundary;
+use automata::agent::TurmiteRule;
+use automata::cell::generations::GenerationsRule;
+use automata::cell::life::LifeRule;
+use automata::cell::ltl::LtlRule;
+use automata::cell::margolus::BlockRule;
+use automata::neighborhood::{Mask, Shape};
+use automata::pattern::{Pattern, Region};
 
 #[derive(Parser, Debug)]
 pub struct AutomataArgs {
     #[command(flatten)]
     pub window: WindowArgs,
 
+    #[command(flatten)]
+    pub headless: HeadlessArgs,
+
     #[arg(
         short = 's',
         long = "cell-size",
@@ -18,6 +28,7 @@ pub struct AutomataArgs {
         short = 't',
         long = "threads",
         default_value_t = 4,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
     )]
     pub threads: usize,
 
@@ -25,6 +36,7 @@ pub struct AutomataArgs {
         short = 'c',
         long = "chunks",
         default_value_t = 32,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
     )]
     pub chunks: usize,
 
@@ -35,8 +47,65 @@ pub struct AutomataArgs {
     )]
     pub gens_per_sec: u32,
 
+    #[arg(
+        value_enum,
+        short = 'b',
+        long = "boundary",
+        default_value_t = Boundary::Dead,
+    )]
+    pub boundary: Boundary,
+
+    #[arg(
+        value_enum,
+        long = "neighborhood",
+        default_value_t = Shape::Moore,
+        conflicts_with = "mask",
+    )]
+    pub neighborhood: Shape,
+
+    #[arg(
+        long = "radius",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u8).range(1..=16),
+        conflicts_with = "mask",
+    )]
+    pub radius: u8,
+
+    #[arg(
+        long = "mask",
+        value_parser = parse_mask,
+    )]
+    pub mask: Option<Mask>,
+
+    #[arg(
+        long = "seed",
+    )]
+    pub seed: Option<u64>,
+
+    #[arg(
+        long = "pattern",
+        value_name = "FILE",
+        value_parser = parse_pattern,
+    )]
+    pub pattern: Option<Pattern>,
+
+    #[arg(
+        long = "offset",
+        value_name = "COL,ROW",
+        value_parser = parse_offset,
+        requires = "pattern",
+    )]
+    pub offset: Option<(usize, usize)>,
+
+    #[arg(
+        long = "load",
+        value_name = "SNAPSHOT",
+        conflicts_with_all = ["seed", "pattern", "cols", "rows", "boundary", "neighborhood", "radius", "mask"],
+    )]
+    pub load: Option<String>,
+
     #[command(subcommand)]
-    pub cell: CellType,
+    pub cell: Option<CellType>,
 }
 
 #[derive(Args, Debug)]
@@ -48,7 +117,7 @@ pub struct WindowArgs {
     #[arg(
         short = 'f',
         long = "fullscreen",
-        conflicts_with_all = ["width", "height"],
+        conflicts_with_all = ["width", "height", "headless"],
     )]
     pub fullscreen: bool,
 
@@ -65,6 +134,67 @@ pub struct WindowArgs {
         requires = "width",
     )]
     pub height: Option<f32>,
+
+    #[arg(
+        long = "headless",
+        conflicts_with_all = ["width", "height"],
+    )]
+    pub headless: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HeadlessArgs {
+    #[arg(
+        long = "cols",
+        requires = "headless",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+    )]
+    pub cols: Option<usize>,
+
+    #[arg(
+        long = "rows",
+        requires = "headless",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+    )]
+    pub rows: Option<usize>,
+
+    #[arg(
+        long = "generations",
+        default_value_t = 1000,
+        requires = "headless",
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub generations: u64,
+
+    #[arg(
+        short = 'o',
+        long = "output",
+        default_value = "automata",
+        requires = "headless",
+    )]
+    pub output: String,
+
+    #[arg(
+        long = "export",
+        value_name = "FILE",
+        requires = "headless",
+    )]
+    pub export: Option<String>,
+
+    #[arg(
+        long = "region",
+        value_name = "COL,ROW,WIDTH,HEIGHT",
+        value_parser = parse_region,
+        requires = "export",
+    )]
+    pub region: Option<Region>,
+
+    #[arg(
+        long = "save",
+        value_name = "FILE",
+        requires = "headless",
+    )]
+    pub save: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -72,6 +202,25 @@ pub enum CellType {
     Life(LifeArgs),
     Cyclic(CyclicArgs),
     Brain(PercentArg),
+    Generations(GenerationsArgs),
+    Ltl(LtlArgs),
+    Wireworld,
+    #[command(alias = "ant")]
+    Turmite(TurmiteArgs),
+    Lenia(LeniaArgs),
+    Smoothlife(SmoothLifeArgs),
+    Elementary(ElementaryArgs),
+    Margolus(MargolusArgs),
+    Sand(SandArgs),
+    Forest(ForestArgs),
+    #[command(alias = "gh")]
+    GreenbergHastings(GreenbergHastingsArgs),
+    Hodgepodge(HodgepodgeArgs),
+    Sandpile(SandpileArgs),
+    Immigration(LifeArgs),
+    Quadlife(LifeArgs),
+    #[command(alias = "rock-paper-scissors")]
+    Rps(RpsArgs),
 }
 
 #[derive(Args, Debug)]
@@ -99,13 +248,252 @@ pub struct LifeArgs {
     pub percent_arg: PercentArg,
 }
 
+#[derive(Args, Debug)]
+pub struct GenerationsArgs {
+    #[arg(
+        short = 'r',
+        long = "rule",
+        default_value = "B2/S/C3",
+        value_parser = parse_generations_rule
+    )]
+    pub rule: GenerationsRule,
+
+    #[clap(flatten)]
+    pub percent_arg: PercentArg,
+}
+
+#[derive(Args, Debug)]
+pub struct LtlArgs {
+    #[arg(
+        short = 'r',
+        long = "rule",
+        default_value = "R5,C0,M1,S34..58,B34..45,NM",
+        value_parser = parse_ltl_rule
+    )]
+    pub rule: LtlRule,
+
+    #[clap(flatten)]
+    pub percent_arg: PercentArg,
+}
+
+#[derive(Args, Debug)]
+pub struct MargolusArgs {
+    #[arg(
+        short = 'r',
+        long = "rule",
+        default_value = "critters",
+        value_parser = parse_block_rule
+    )]
+    pub rule: BlockRule,
+
+    #[clap(flatten)]
+    pub percent_arg: PercentArg,
+}
+
+#[derive(Args, Debug)]
+pub struct SandArgs {
+    #[arg(
+        long = "sand",
+        default_value_t = 25,
+        value_parser = clap::value_parser!(u8).range(0..=100),
+    )]
+    pub sand: u8,
+
+    #[arg(
+        long = "water",
+        default_value_t = 25,
+        value_parser = clap::value_parser!(u8).range(0..=100),
+    )]
+    pub water: u8,
+}
+
+#[derive(Args, Debug)]
+pub struct ForestArgs {
+    #[arg(
+        long = "growth",
+        default_value_t = 0.01,
+        value_parser = parse_probability,
+    )]
+    pub growth: f32,
+
+    #[arg(
+        long = "lightning",
+        default_value_t = 0.00001,
+        value_parser = parse_probability,
+    )]
+    pub lightning: f32,
+
+    #[clap(flatten)]
+    pub percent_arg: PercentArg,
+}
+
+#[derive(Args, Debug)]
+pub struct TurmiteArgs {
+    #[arg(
+        short = 'r',
+        long = "rule",
+        default_value = "RL",
+        value_parser = parse_turmite_rule
+    )]
+    pub rule: TurmiteRule,
+
+    #[arg(
+        short = 'a',
+        long = "ants",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u16).range(1..),
+    )]
+    pub ants: u16,
+
+    #[arg(
+        value_enum,
+        short = 'p',
+        long = "palette",
+        default_value_t = Palette::Grayscale,
+    )]
+    pub palette: Palette,
+}
+
+#[derive(Args, Debug)]
+pub struct LeniaArgs {
+    #[arg(
+        short = 'R',
+        long = "range",
+        default_value_t = 13,
+        value_parser = clap::value_parser!(u8).range(2..=32),
+    )]
+    pub range: u8,
+
+    #[arg(
+        long = "peaks",
+        default_value = "1",
+        value_delimiter = ',',
+        value_parser = parse_peak,
+    )]
+    pub peaks: Vec<f32>,
+
+    #[arg(
+        long = "mu",
+        default_value_t = 0.15,
+    )]
+    pub mu: f32,
+
+    #[arg(
+        long = "sigma",
+        default_value_t = 0.015,
+        value_parser = parse_sigma,
+    )]
+    pub sigma: f32,
+
+    #[arg(
+        long = "dt",
+        default_value_t = 0.1,
+        value_parser = parse_time_step,
+    )]
+    pub dt: f32,
+
+    #[clap(flatten)]
+    pub percent_arg: PercentArg,
+}
+
+#[derive(Args, Debug)]
+pub struct SmoothLifeArgs {
+    #[arg(
+        short = 'R',
+        long = "range",
+        default_value_t = 12,
+        value_parser = clap::value_parser!(u8).range(3..=32),
+    )]
+    pub range: u8,
+
+    #[arg(
+        long = "birth",
+        default_value = "0.278..0.365",
+        value_parser = parse_interval,
+    )]
+    pub birth: (f32, f32),
+
+    #[arg(
+        long = "survive",
+        default_value = "0.267..0.445",
+        value_parser = parse_interval,
+    )]
+    pub survive: (f32, f32),
+
+    #[arg(
+        long = "alpha-n",
+        default_value_t = 0.028,
+        value_parser = parse_smoothness,
+    )]
+    pub alpha_n: f32,
+
+    #[arg(
+        long = "alpha-m",
+        default_value_t = 0.147,
+        value_parser = parse_smoothness,
+    )]
+    pub alpha_m: f32,
+
+    #[arg(
+        long = "dt",
+        default_value_t = 1.0,
+        value_parser = parse_time_step,
+    )]
+    pub dt: f32,
+
+    #[arg(
+        short = 'p',
+        long = "percentage",
+        default_value_t = 20,
+        value_parser = clap::value_parser!(u8).range(0..=100),
+    )]
+    pub percentage: u8,
+}
+
+#[derive(Args, Debug)]
+pub struct ElementaryArgs {
+    #[arg(
+        short = 'r',
+        long = "rule",
+        default_value_t = 110,
+    )]
+    pub rule: u64,
+
+    #[arg(
+        short = 'k',
+        long = "colors",
+        default_value_t = 2,
+        value_parser = clap::value_parser!(u8).range(2..=16),
+    )]
+    pub colors: u8,
+
+    #[arg(
+        long = "totalistic",
+    )]
+    pub totalistic: bool,
+
+    #[arg(
+        short = 'p',
+        long = "percentage",
+        value_parser = clap::value_parser!(u8).range(0..=100),
+    )]
+    pub percentage: Option<u8>,
+
+    #[arg(
+        value_enum,
+        long = "palette",
+        default_value_t = Palette::Grayscale,
+    )]
+    pub palette: Palette,
+}
+
 #[derive(Args, Debug)]
 pub struct CyclicArgs {
     #[arg(
         short = 'n',
         long = "threshold",
         default_value_t = 1,
-        value_parser = clap::value_parser!(u8).range(1..=8),
+        value_parser = clap::value_parser!(u8).range(1..),
     )]
     pub threshold: u8,
 
@@ -118,6 +506,128 @@ pub struct CyclicArgs {
     pub palette: Palette,
 }
 
+#[derive(Args, Debug)]
+pub struct GreenbergHastingsArgs {
+    #[arg(
+        short = 'n',
+        long = "threshold",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u8).range(1..),
+    )]
+    pub threshold: u8,
+
+    #[arg(
+        short = 'r',
+        long = "refractory",
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u8).range(1..=253),
+    )]
+    pub refractory: u8,
+
+    #[arg(
+        value_enum,
+        short = 'p',
+        long = "palette",
+        default_value_t = Palette::Grayscale,
+    )]
+    pub palette: Palette,
+}
+
+#[derive(Args, Debug)]
+pub struct HodgepodgeArgs {
+    #[arg(
+        long = "states",
+        default_value_t = 100,
+        value_parser = clap::value_parser!(u16).range(3..=256),
+    )]
+    pub states: u16,
+
+    #[arg(
+        long = "k1",
+        default_value_t = 2,
+        value_parser = clap::value_parser!(u8).range(1..),
+    )]
+    pub k1: u8,
+
+    #[arg(
+        long = "k2",
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u8).range(1..),
+    )]
+    pub k2: u8,
+
+    #[arg(
+        short = 'g',
+        long = "g",
+        default_value_t = 34,
+    )]
+    pub g: u8,
+
+    #[arg(
+        value_enum,
+        short = 'p',
+        long = "palette",
+        default_value_t = Palette::Grayscale,
+    )]
+    pub palette: Palette,
+}
+
+#[derive(Args, Debug)]
+pub struct SandpileArgs {
+    #[arg(
+        long = "pile",
+        default_value_t = 10000,
+    )]
+    pub pile: u32,
+
+    #[arg(
+        long = "relax",
+    )]
+    pub relax: bool,
+
+    #[arg(
+        value_enum,
+        short = 'p',
+        long = "palette",
+        default_value_t = Palette::Grayscale,
+    )]
+    pub palette: Palette,
+}
+
+#[derive(Args, Debug)]
+pub struct RpsArgs {
+    #[arg(
+        short = 's',
+        long = "species",
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u8).range(2..),
+    )]
+    pub species: u8,
+
+    #[arg(
+        short = 'n',
+        long = "threshold",
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u8).range(1..),
+    )]
+    pub threshold: u8,
+
+    #[arg(
+        short = 'r',
+        long = "random",
+        default_value_t = 0,
+    )]
+    pub randomness: u8,
+
+    #[arg(
+        value_enum,
+        short = 'p',
+        long = "palette",
+        default_value_t = Palette::Grayscale,
+    )]
+    pub palette: Palette,
+}
+
 #[derive(Clone, ValueEnum, Debug)]
 pub enum Palette {
     Rainbow,
@@ -128,3 +638,95 @@ fn parse_rule(s: &str) -> Result<LifeRule, String> {
     LifeRule::from_str(s)
         .map_err(|rule_err| rule_err.message.to_owned())
 }
+
+fn parse_generations_rule(s: &str) -> Result<GenerationsRule, String> {
+    GenerationsRule::from_str(s)
+        .map_err(|rule_err| rule_err.message.to_owned())
+}
+
+fn parse_ltl_rule(s: &str) -> Result<LtlRule, String> {
+    LtlRule::from_str(s)
+        .map_err(|rule_err| rule_err.message.to_owned())
+}
+
+fn parse_turmite_rule(s: &str) -> Result<TurmiteRule, String> {
+    TurmiteRule::from_str(s)
+        .map_err(|rule_err| rule_err.message.to_owned())
+}
+
+fn parse_block_rule(s: &str) -> Result<BlockRule, String> {
+    BlockRule::from_str(s)
+        .map_err(|rule_err| rule_err.message.to_owned())
+}
+
+fn parse_probability(s: &str) -> Result<f32, String> {
+    s.trim().parse::<f32>().ok()
+        .filter(|probability| (0.0..=1.0).contains(probability))
+        .ok_or_else(|| "probabilities must be numbers from 0 through 1".to_owned())
+}
+
+fn parse_peak(s: &str) -> Result<f32, String> {
+    s.trim().parse::<f32>().ok()
+        .filter(|peak| (0.0..=1.0).contains(peak))
+        .ok_or_else(|| "peaks must be numbers from 0 through 1".to_owned())
+}
+
+fn parse_sigma(s: &str) -> Result<f32, String> {
+    s.trim().parse::<f32>().ok()
+        .filter(|sigma| sigma.is_finite() && *sigma > 0.0)
+        .ok_or_else(|| "sigma must be a number greater than 0".to_owned())
+}
+
+fn parse_smoothness(s: &str) -> Result<f32, String> {
+    s.trim().parse::<f32>().ok()
+        .filter(|alpha| alpha.is_finite() && *alpha > 0.0)
+        .ok_or_else(|| "smoothness must be a number greater than 0".to_owned())
+}
+
+fn parse_time_step(s: &str) -> Result<f32, String> {
+    s.trim().parse::<f32>().ok()
+        .filter(|dt| *dt > 0.0 && *dt <= 1.0)
+        .ok_or_else(|| "time steps must be numbers greater than 0, up to 1".to_owned())
+}
+
+fn parse_interval(s: &str) -> Result<(f32, f32), String> {
+    s.split_once("..")
+        .and_then(|(min, max)| Some((min.trim().parse().ok()?, max.trim().parse().ok()?)))
+        .ok_or_else(|| "interval must be of the form 'MIN..MAX'".to_owned())
+}
+
+fn parse_mask(s: &str) -> Result<Mask, String> {
+    Mask::from_str(s)
+        .map_err(|mask_err| mask_err.message.to_owned())
+}
+
+fn parse_pattern(path: &str) -> Result<Pattern, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|io_err| io_err.to_string())?;
+
+    // plaintext patterns are '.cells' or '.txt' files, anything else is RLE
+    let pattern = match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("cells" | "txt") => Pattern::from_plaintext(&contents),
+        _ => Pattern::from_str(&contents),
+    };
+
+    pattern.map_err(|pattern_err| pattern_err.message.to_owned())
+}
+
+fn parse_offset(s: &str) -> Result<(usize, usize), String> {
+    s.split_once(',')
+        .and_then(|(col, row)| Some((col.trim().parse().ok()?, row.trim().parse().ok()?)))
+        .ok_or_else(|| "offset must be of the form 'COL,ROW'".to_owned())
+}
+
+fn parse_region(s: &str) -> Result<Region, String> {
+    let values: Vec<usize> = s.split(',')
+        .map(|value| value.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| "region must be of the form 'COL,ROW,WIDTH,HEIGHT'".to_owned())?;
+
+    match values[..] {
+        [col, row, width, height] => Ok(Region { col, row, width, height }),
+        _ => Err("region must be of the form 'COL,ROW,WIDTH,HEIGHT'".to_owned()),
+    }
+}
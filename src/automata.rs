@@ -1,75 +1,229 @@
 use std::thread;
-use std::sync::Arc;
-use std::sync::mpsc::{Sender, Receiver, channel};
 use std::ops::Range;
-use macroquad::prelude::*;
-use crate::SCREEN_DIMS;
-use crate::cell::Cell;
+use crate::agent::{Agents, AGENT_COLOR};
+use crate::boundary::{Boundary, Neighbor};
+use crate::neighborhood::Neighborhood;
+use crate::pattern::{Pattern, PatternError, Region};
+use crate::cell::{Cell, MoveFn, Neighbors, NextFn, UpdateMode};
+use crate::color::Color;
+use crate::render::Renderer;
+use crate::rng::Rng;
+use crate::snapshot::{self, Reader, SnapshotError, Writer};
+use crate::sums::NeighborSums;
+
+/// The most passes over the grid a relaxing generation makes before it
+/// carries on in the next generation.
+pub const RELAX_PASSES: usize = 1 << 16;
 
 pub trait AutomataTrait {
     fn next(&mut self);
-    fn render(&self);
+    fn render(&mut self);
+    fn reset(&mut self);
+    fn load_pattern(&mut self, pattern: &Pattern, offset: Option<(usize, usize)>) -> Result<(), PatternError>;
+    fn to_pattern(&self, region: Option<Region>) -> Pattern;
+    fn save_snapshot(&self) -> Vec<u8>;
+    fn attach_renderer(&mut self, renderer: Box<dyn Renderer>);
+    fn grid_size(&self) -> GridSize;
+    fn changed(&self) -> usize;
+    /// The number of cells that toppled last generation, for cell types that
+    /// topple like sandpiles.
+    fn topplings(&self) -> Option<usize>;
+    fn generation(&self) -> u64;
+    fn color(&self, col: usize, row: usize) -> Color;
+    fn paint(&mut self, col: usize, row: usize, brush: Brush);
+    fn next_paint_state(&mut self);
+    fn paint_color(&self) -> Color;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Brush {
+    Paint,
+    Erase,
 }
 
 #[derive(Clone, Copy)]
-struct GridSize {
+pub struct GridSize {
     pub cols: usize,
     pub rows: usize,
 }
 
-struct WorkerItem<C>
-where C: 'static + Cell + Send + Sync + Default + PartialEq + Eq,
-      C::Params: 'static + Send + Sync
-{
-    pub current: Arc<Box<[C]>>,
-    pub range: Range<usize>,
-    pub result_send: Sender<(usize, C)>,
+struct WorkerItem<'a, C> {
+    pub start: usize,
+    pub next: &'a mut [C],
 }
 
-struct AutomataTexture {
-    image: Image,
-    texture: Texture2D,
-    params: DrawTextureParams,
-    pub first_update: bool,
+struct WorkerContext<'a, C: Cell> {
+    pub grid_size: GridSize,
+    pub boundary: Boundary,
+    pub offsets: &'a [(isize, isize)],
+    pub cell_params: &'a C::Params,
+    pub current: &'a [C],
+    pub sums: Option<&'a NeighborSums>,
+    /// Whether block cells use the blocks shifted by one cell
+    pub odd: bool,
+    /// Seeds the random number generator of each cell this generation
+    pub seed: u64,
 }
 
 pub struct AutomataParams {
-    pub cell_size: f32,
+    pub grid_size: GridSize,
     pub threads: usize,
     pub chunks: usize,
+    pub boundary: Boundary,
+    pub neighborhood: Neighborhood,
+    pub seed: u64,
 }
 
-#[allow(dead_code)] // ignore warning for 'workers' never read
 pub struct Automata<C>
-where C: 'static + Cell + Send + Sync + Default + PartialEq + Eq,
+where C: 'static + Cell + Send + Sync + Clone + Default + PartialEq,
       C::Params: 'static + Send + Sync
 {
     grid_size: GridSize,
-    cell_params: Arc<C::Params>,
-    current: Arc<Box<[C]>>,
+    cell_params: C::Params,
+    current: Box<[C]>,
     next: Box<[C]>,
     chunks: Box<[Range<usize>]>,
-    workers: Box<[thread::JoinHandle<()>]>,
-    item_senders: Box<[Sender<WorkerItem<C>>]>,
-    texture: AutomataTexture,
+    threads: usize,
+    boundary: Boundary,
+    offsets: Box<[(isize, isize)]>,
+    mode: UpdateMode<C>,
+    sums: Option<NeighborSums>,
+    agents: Option<Agents>,
+    changed: usize,
+    topplings: usize,
+    generation: u64,
+    rng: Rng,
+    paint_states: Box<[C]>,
+    paint_state: usize,
+    renderer: Option<Box<dyn Renderer>>,
 }
 
 impl<C> Automata<C>
-where C: 'static + Cell + Send + Sync + Default + PartialEq + Eq,
+where C: 'static + Cell + Send + Sync + Clone + Default + PartialEq,
       C::Params: 'static + Send + Sync
 {
     pub fn new(params: AutomataParams, cell_params: C::Params) -> Automata<C> {
-        let AutomataParams { cell_size, threads, chunks } = params;
+        let AutomataParams { grid_size, threads, chunks, boundary, neighborhood, seed } = params;
+
+        let mut rng = Rng::new(seed);
+        let current = C::initial(&cell_params, grid_size, &mut rng);
+
+        let neighborhood = C::neighborhood(&cell_params).unwrap_or(neighborhood);
+        Automata::from_parts(grid_size, threads, chunks, boundary, neighborhood.offsets(), cell_params, current, rng)
+    }
+
+    /// Resumes an automata from a snapshot, after the header has been read
+    /// with `snapshot::read_header`.
+    pub fn from_snapshot(reader: &mut Reader, threads: usize, chunks: usize) -> Result<Automata<C>, SnapshotError> {
+        let invalid = SnapshotError {
+            message: "snapshot is truncated or invalid",
+        };
 
-        // determine the grid size based on screen and cell size
-        let (sw, sh) = *SCREEN_DIMS;
-        let cols = (sw / cell_size) as usize;
-        let rows = (sh / cell_size) as usize;
+        let grid_size = GridSize {
+            cols: reader.u64().ok_or(invalid)? as usize,
+            rows: reader.u64().ok_or(invalid)? as usize,
+        };
+
+        let generation = reader.u64().ok_or(invalid)?;
+        let rng = Rng::new(reader.u64().ok_or(invalid)?);
+        let boundary = reader.u8()
+            .and_then(snapshot::boundary_from_u8)
+            .ok_or(invalid)?;
+
+        // every cell takes at least one byte, so the grid cannot be larger
+        // than the rest of the snapshot
+        let total = grid_size.cols.checked_mul(grid_size.rows)
+            .filter(|&total| total > 0 && total <= reader.len())
+            .ok_or(invalid)?;
+
+        let offsets_len = reader.u64().ok_or(invalid)? as usize;
+        if offsets_len > reader.len() / 16 {
+            return Err(invalid);
+        }
+
+        let offsets = (0..offsets_len)
+            .map(|_| Some((reader.i64()? as isize, reader.i64()? as isize)))
+            .collect::<Option<Box<[(isize, isize)]>>>()
+            .ok_or(invalid)?;
+
+        let cell_params = C::read_params(reader).ok_or(invalid)?;
+        if boundary.is_closed() && matches!(C::update_mode(&cell_params), UpdateMode::Topple { relax: true, .. }) {
+            return Err(SnapshotError {
+                message: "snapshot relaxes on a boundary that grains cannot leave",
+            });
+        }
+
+        if overlapping_blocks(grid_size, boundary, &C::update_mode(&cell_params)) {
+            return Err(SnapshotError {
+                message: "snapshot has blocks that overlap where the grid wraps",
+            });
+        }
 
-        let total = cols * rows;
-        let (current, next): (Vec<C>, Vec<C>) = (0..total)
-            .map(|_| (C::new(&cell_params), C::default()))
-            .unzip();
+        let current = (0..total)
+            .map(|_| C::read(reader, &cell_params))
+            .collect::<Option<Vec<C>>>()
+            .ok_or(invalid)?;
+
+        let agents = match reader.u8().ok_or(invalid)? {
+            0 => None,
+            _ => Some(Agents::read(reader, grid_size).ok_or(invalid)?),
+        };
+
+        // agents paint every color of their rule, so the cells need exactly
+        // that many states
+        let matches_cells = |agents: &Agents| {
+            let colors = agents.colors();
+            C::from_state(colors - 1, &cell_params).is_some() && C::from_state(colors, &cell_params).is_none()
+        };
+
+        if agents.as_ref().is_some_and(|agents| !matches_cells(agents)) {
+            return Err(SnapshotError {
+                message: "snapshot cells do not have the colors of the agents' rule",
+            });
+        }
+
+        if !reader.is_empty() {
+            return Err(invalid);
+        }
+
+        let mut automata = Automata::from_parts(grid_size, threads, chunks, boundary, offsets, cell_params, current, rng);
+        automata.generation = generation;
+        automata.agents = agents;
+        Ok(automata)
+    }
+
+    /// Adds agents that move over the grid after each generation.
+    pub fn with_agents(mut self, agents: Agents) -> Automata<C> {
+        self.agents = Some(agents);
+        self.redraw();
+        self
+    }
+
+    /// Draws every cell, with the agents on top.
+    fn redraw(&mut self) {
+        if let Some(renderer) = &mut self.renderer {
+            redraw(renderer.as_mut(), self.grid_size, &self.cell_params, &self.current);
+            for agent in self.agents.iter().flat_map(Agents::agents) {
+                renderer.set_pixel(agent.col, agent.row, AGENT_COLOR);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn from_parts(
+        grid_size: GridSize,
+        threads: usize,
+        chunks: usize,
+        boundary: Boundary,
+        offsets: Box<[(isize, isize)]>,
+        cell_params: C::Params,
+        current: Vec<C>,
+        rng: Rng,
+    ) -> Automata<C> {
+        assert!(threads > 0 && chunks > 0, "automata need at least one thread and chunk");
+        assert!(!overlapping_blocks(grid_size, boundary, &C::update_mode(&cell_params)), "blocks need an even number of columns and rows to wrap");
+        let total = current.len();
+        let next = vec![C::default(); total];
 
         let chunk_size = total / chunks;
         let mut chunks_vec: Vec<Range<usize>> = (0..chunks - 1)
@@ -80,164 +234,649 @@ where C: 'static + Cell + Send + Sync + Default + PartialEq + Eq,
             .collect();
         chunks_vec.push((chunk_size * (chunks - 1))..total);
 
-        let grid_size = GridSize { cols, rows };
-        let cell_params = Arc::new(cell_params);
-        let (workers, senders): (Vec<thread::JoinHandle<()>>, Vec<Sender<WorkerItem<C>>>) = (0..threads)
-            .map(|_| {
-                let cell_params_clone = Arc::clone(&cell_params);
-                let (item_send, item_recv) = channel();
-                let handle = thread::spawn(move || {
-                    Automata::calculate_chunk(grid_size, cell_params_clone, item_recv);
-                });
-
-                (handle, item_send)
-            })
-            .unzip();
-
-        let image = Image::gen_image_color(cols as u16, rows as u16, BLACK);
-        let texture = Texture2D::from_image(&image);
-        texture.set_filter(FilterMode::Nearest);
+        let paint_states = C::paint_states(&cell_params).into_boxed_slice();
+        let mode = C::update_mode(&cell_params);
+        let sums = matches!(mode, UpdateMode::Counts { .. })
+            .then(|| NeighborSums::new(grid_size, &offsets));
 
         Automata {
             grid_size,
             cell_params,
-            current: Arc::new(current.into_boxed_slice()),
+            current: current.into_boxed_slice(),
             next: next.into_boxed_slice(),
             chunks: chunks_vec.into_boxed_slice(),
-            workers: workers.into_boxed_slice(),
-            item_senders: senders.into_boxed_slice(),
-            texture: AutomataTexture {
-                image,
-                texture,
-                params: DrawTextureParams {
-                    dest_size: Some((*SCREEN_DIMS).into()),
-                    ..DrawTextureParams::default()
-                },
-                first_update: true,
-            },
+            threads,
+            boundary,
+            offsets,
+            mode,
+            sums,
+            agents: None,
+            changed: 0,
+            topplings: 0,
+            generation: 0,
+            rng,
+            paint_states,
+            paint_state: 0,
+            renderer: None,
         }
     }
 
-    fn calculate_chunk(
-        grid_size: GridSize,
-        cell_params: Arc<C::Params>,
-        item_recv: Receiver<WorkerItem<C>>
-    ) {
-        static DIFFS: [(isize, isize); 8] = [
-            (-1, -1), (0, -1), (1, -1),
-            (-1,  0),          (1,  0),
-            (-1,  1), (0,  1), (1,  1)
-        ];
+    /// Calculates the next generation of the cells in `item` from their
+    /// neighbors and records the indices of cells that changed in `changed`.
+    fn calculate_neighbors(context: &WorkerContext<C>, next: NextFn<C>, item: WorkerItem<C>, changed: &mut Vec<usize>) {
+        let WorkerContext { offsets, cell_params, current, seed, .. } = *context;
+        let WorkerItem { start, next: next_cells } = item;
+        let fixed = C::default();
+
+        let mut neighbors: Vec<Option<&C>> = vec![None; offsets.len()];
+        for (index, next_cell) in (start..).zip(next_cells.iter_mut()) {
+            Automata::gather(context, &fixed, index, &mut neighbors);
+
+            // calculate next cell
+            let mut rng = Rng::for_cell(seed, index);
+            *next_cell = next(&current[index], cell_params, Neighbors::new(offsets, &neighbors), &mut rng);
+
+            // if the cell is different from last generation, it needs to be redrawn
+            if next_cell.differs(&current[index]) {
+                changed.push(index);
+            }
+        }
+    }
 
+    /// Calculates the next generation of the cells in `item` from the total
+    /// weight of their neighbors, which only needs the sums.
+    fn calculate_counts(context: &WorkerContext<C>, next: fn(&C, &C::Params, u32) -> C, item: WorkerItem<C>, changed: &mut Vec<usize>) {
+        let WorkerContext { grid_size, cell_params, current, sums, .. } = *context;
+        let WorkerItem { start, next: next_cells } = item;
+
+        // SAFETY: the sums are kept for every cell type that counts neighbors
+        let sums = sums.unwrap();
+        for (index, next_cell) in (start..).zip(next_cells.iter_mut()) {
+            let (col, row) = linear_to_grid(grid_size.rows, index);
+            *next_cell = next(&current[index], cell_params, sums.sum(col, row));
+            if next_cell.differs(&current[index]) {
+                changed.push(index);
+            }
+        }
+    }
+
+    /// Calculates the next block of every block in `next`, starting from
+    /// block `start`. Blocks that go past the edge of the grid, or cover the
+    /// same cell twice, are left as `None`.
+    fn calculate_blocks(
+        context: &WorkerContext<C>,
+        next_block: fn(&C::Params, [&C; 4]) -> [C; 4],
+        block_rows: usize,
+        start: usize,
+        next: &mut [Option<[C; 4]>],
+    ) {
+        let WorkerContext { grid_size, boundary, cell_params, current, odd, .. } = *context;
         let GridSize { cols, rows } = grid_size;
-        let total = cols * rows;
-
-        let mut neighbors: [Option<&C>; 8];
-        while let Ok(WorkerItem { current, range, result_send }) = item_recv.recv() {
-            for index in range.into_iter() {
-                neighbors = [None; 8];
-                let neighbor_diff_iter = DIFFS.iter().zip(neighbors.iter_mut());
-
-                for (diff, nopt) in neighbor_diff_iter {
-                    let nindex = match diff {
-                        (-1,  _) if index < rows => continue,
-                        ( 1,  _) if total - index <= rows => continue,
-                        ( _, -1) if (index % rows) == 0 => continue,
-                        ( _,  1) if ((index + 1) % rows) == 0 => continue,
-                        (-1, drow) => ((index - rows) as isize + drow) as usize,
-                        ( 0, drow) => (index as isize + drow) as usize,
-                        ( 1, drow) => ((index + rows) as isize + drow) as usize,
-                        _ => continue
-                    };
-
-                    *nopt = Some(&current[nindex]);
+        let shift = odd as isize;
+
+        for (block, next_block_cells) in (start..).zip(next.iter_mut()) {
+            let (block_col, block_row) = linear_to_grid(block_rows, block);
+            let (block_col, block_row) = (2 * block_col as isize - shift, 2 * block_row as isize - shift);
+
+            let mut indices = [0; 4];
+            let inside = indices.iter_mut().enumerate().all(|(corner, index)| {
+                let (ccol, crow) = (block_col + (corner % 2) as isize, block_row + (corner / 2) as isize);
+                match boundary.resolve(cols, rows, ccol, crow) {
+                    Neighbor::Index(cindex) => {
+                        *index = cindex;
+                        true
+                    },
+                    Neighbor::Fixed | Neighbor::Outside => false,
                 }
+            });
+
+            let distinct = (0..4).all(|a| (a + 1..4).all(|b| indices[a] != indices[b]));
+            *next_block_cells = (inside && distinct)
+                .then(|| next_block(cell_params, indices.map(|index| &current[index])));
+        }
+    }
 
-                // calculate next cell
-                let neighbors_iter = neighbors.iter_mut()
-                    .flat_map(|nopt| nopt.take());
-                let next = current[index].next(&cell_params, neighbors_iter);
+    /// Writes each cell of `item` from the corner of its block in `blocks`,
+    /// or leaves it as it is if its block was left alone.
+    fn settle_blocks(context: &WorkerContext<C>, blocks: &[Option<[C; 4]>], block_rows: usize, item: WorkerItem<C>, changed: &mut Vec<usize>) {
+        let WorkerContext { grid_size, current, odd, .. } = *context;
+        let WorkerItem { start, next } = item;
+        let shift = odd as usize;
 
-                // SAFETY: receiver is only dropped when all senders are dropped
-                result_send.send((index, next)).unwrap();
+        for (index, next_cell) in (start..).zip(next.iter_mut()) {
+            let (col, row) = linear_to_grid(grid_size.rows, index);
+            let (col, row) = (col + shift, row + shift);
+            let corner = (row % 2) * 2 + col % 2;
+
+            *next_cell = match &blocks[(col / 2) * block_rows + row / 2] {
+                Some(block) => block[corner].clone(),
+                None => current[index].clone(),
+            };
+
+            if next_cell.differs(&current[index]) {
+                changed.push(index);
             }
+        }
+    }
+
+    /// Splits `cells` into the chunks and deals them out to each thread.
+    fn deal<'a, T>(&self, cells: &'a mut [T]) -> Vec<Vec<WorkerItem<'a, T>>> {
+        let mut items: Vec<Vec<WorkerItem<T>>> = (0..self.threads)
+            .map(|_| Vec::new())
+            .collect();
+
+        let mut rest = cells;
+        let chunk_items_iter = self.chunks.iter()
+            .zip((0..self.threads).cycle());
+        for (range, thread) in chunk_items_iter {
+            let (next, tail) = rest.split_at_mut(range.len());
+            items[thread].push(WorkerItem { start: range.start, next });
+            rest = tail;
+        }
+
+        items
+    }
 
-            // drop reference to current before dropping result sender
-            drop(current);
-            drop(result_send);
+    fn context(&self, seed: u64) -> WorkerContext<'_, C> {
+        WorkerContext {
+            grid_size: self.grid_size,
+            boundary: self.boundary,
+            offsets: &self.offsets,
+            cell_params: &self.cell_params,
+            current: &self.current,
+            sums: self.sums.as_ref(),
+            odd: self.generation % 2 == 1,
+            seed,
         }
     }
 
+    /// Calculates the next generation of every cell in place with
+    /// `calculate`, returning the indices of cells that changed.
+    fn next_cells(&mut self, seed: u64, calculate: impl Fn(&WorkerContext<C>, WorkerItem<C>, &mut Vec<usize>) + Sync) -> Vec<Vec<usize>> {
+        // split the next generation into disjoint chunks and deal them out
+        // to each thread
+        let mut next = std::mem::take(&mut self.next);
+        let items = self.deal(&mut next);
+
+        // calculate the next generation, writing directly into the chunks
+        let context = &self.context(seed);
+        let calculate = &calculate;
+        let changed = thread::scope(|scope| {
+            let handles: Vec<_> = items.into_iter()
+                .map(|thread_items| scope.spawn(move || {
+                    let mut changed = Vec::new();
+                    for item in thread_items {
+                        calculate(context, item, &mut changed);
+                    }
+
+                    changed
+                }))
+                .collect();
+
+            // SAFETY: worker threads do not panic
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        self.next = std::mem::replace(&mut self.current, next);
+        changed
+    }
+
+    /// Calculates the next generation of block cells, returning the indices
+    /// of cells that changed.
+    ///
+    /// First every block is calculated once, then each cell is written from
+    /// the block it is in. The blocks shift by one cell every other
+    /// generation, which is why wrapping grids need an even number of columns
+    /// and rows.
+    fn next_blocks(&mut self, seed: u64, next_block: fn(&C::Params, [&C; 4]) -> [C; 4]) -> Vec<Vec<usize>> {
+        let GridSize { cols, rows } = self.grid_size;
+        let shift = (self.generation % 2) as usize;
+        let block_rows = (rows + shift).div_ceil(2);
+        let total = (cols + shift).div_ceil(2) * block_rows;
+
+        // calculate each block, splitting them evenly between the threads
+        let mut blocks: Vec<Option<[C; 4]>> = (0..total).map(|_| None).collect();
+        let context = &self.context(seed);
+        let chunk_size = total.div_ceil(self.threads).max(1);
+        thread::scope(|scope| {
+            for (n, next) in blocks.chunks_mut(chunk_size).enumerate() {
+                scope.spawn(move || {
+                    Automata::calculate_blocks(context, next_block, block_rows, n * chunk_size, next);
+                });
+            }
+        });
+
+        // then write every cell from its block
+        let blocks = &blocks;
+        self.next_cells(seed, move |context, item, changed| {
+            Automata::settle_blocks(context, blocks, block_rows, item, changed);
+        })
+    }
+
+    /// Moves cells that swap places with their neighbors, returning the
+    /// indices of cells that changed.
+    ///
+    /// First every cell picks a neighbor to swap with, then each cell that
+    /// nobody leaves goes to the first neighbor in the neighborhood that
+    /// picked it. Cells that lose, or pick a neighbor that is moving itself,
+    /// stay where they are.
+    fn next_moves(&mut self, seed: u64, next_move: MoveFn<C>) -> Vec<Vec<usize>> {
+        let mut next = std::mem::take(&mut self.next);
+        let mut targets = vec![None; self.current.len()];
+
+        // pick where each cell wants to go
+        let items = self.deal(&mut next).into_iter()
+            .zip(self.deal(&mut targets));
+
+        let context = &self.context(seed);
+        thread::scope(|scope| {
+            for (thread_items, thread_targets) in items {
+                scope.spawn(move || {
+                    for (item, targets) in thread_items.into_iter().zip(thread_targets) {
+                        Automata::calculate_moves(context, next_move, item, targets.next);
+                    }
+                });
+            }
+        });
+        // then settle who ends up in each cell, writing over the current
+        // generation since only the picks are needed
+        let mut current = std::mem::take(&mut self.current);
+        let items = self.deal(&mut current);
+        let (grid_size, boundary, offsets) = (self.grid_size, self.boundary, &*self.offsets);
+        let (moved, targets) = (&next, &targets);
+        let changed = thread::scope(|scope| {
+            let handles: Vec<_> = items.into_iter()
+                .map(|thread_items| scope.spawn(move || {
+                    let mut changed = Vec::new();
+                    for item in thread_items {
+                        Automata::settle_moves(grid_size, boundary, offsets, moved, targets, item, &mut changed);
+                    }
+
+                    changed
+                }))
+                .collect();
+
+            // SAFETY: worker threads do not panic
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        self.current = current;
+        self.next = next;
+        changed
+    }
+
+    /// Fills in the neighbors of the cell at `index`, in the order of the
+    /// offsets.
+    fn gather<'a>(context: &WorkerContext<'a, C>, fixed: &'a C, index: usize, neighbors: &mut [Option<&'a C>]) {
+        let WorkerContext { grid_size, boundary, offsets, current, .. } = *context;
+        let GridSize { cols, rows } = grid_size;
+
+        let (col, row) = linear_to_grid(rows, index);
+        for ((dcol, drow), nopt) in offsets.iter().zip(neighbors.iter_mut()) {
+            let ncol = col as isize + dcol;
+            let nrow = row as isize + drow;
+            *nopt = match boundary.resolve(cols, rows, ncol, nrow) {
+                Neighbor::Index(nindex) => Some(&current[nindex]),
+                Neighbor::Fixed => Some(fixed),
+                Neighbor::Outside => None,
+            };
+        }
+    }
+
+    /// Records the cell each cell in `item` wants to swap with in `targets`,
+    /// and the cell as it will be in `item`.
+    fn calculate_moves(context: &WorkerContext<C>, next_move: MoveFn<C>, item: WorkerItem<C>, targets: &mut [Option<usize>]) {
+        let WorkerContext { grid_size, boundary, offsets, cell_params, current, .. } = *context;
+        let GridSize { cols, rows } = grid_size;
+        let WorkerItem { start, next } = item;
+        let fixed = C::default();
+
+        let mut neighbors: Vec<Option<&C>> = vec![None; offsets.len()];
+        let items_iter = (start..).zip(next.iter_mut()).zip(targets.iter_mut());
+        for ((index, next_cell), target) in items_iter {
+            Automata::gather(context, &fixed, index, &mut neighbors);
+            let (cell, offset) = next_move(&current[index], cell_params, Neighbors::new(offsets, &neighbors));
+            *next_cell = cell;
+
+            // cells can only swap with other cells in the grid
+            let (col, row) = linear_to_grid(rows, index);
+            *target = offset.and_then(|(dcol, drow)| {
+                match boundary.resolve(cols, rows, col as isize + dcol, row as isize + drow) {
+                    Neighbor::Index(tindex) if tindex != index => Some(tindex),
+                    _ => None,
+                }
+            });
+        }
+    }
+
+    /// Writes the cell that ends up in each cell of `item`, given the `moved`
+    /// cells and their `targets`, and records the indices of cells that
+    /// changed.
+    fn settle_moves(
+        grid_size: GridSize,
+        boundary: Boundary,
+        offsets: &[(isize, isize)],
+        moved: &[C],
+        targets: &[Option<usize>],
+        item: WorkerItem<C>,
+        changed: &mut Vec<usize>,
+    ) {
+        let GridSize { cols, rows } = grid_size;
+        let WorkerItem { start, next } = item;
+
+        // the first neighbor to pick a cell wins it
+        let winner = |index: usize| {
+            let (col, row) = linear_to_grid(rows, index);
+            offsets.iter().find_map(|(dcol, drow)| {
+                match boundary.resolve(cols, rows, col as isize + dcol, row as isize + drow) {
+                    Neighbor::Index(nindex) if targets[nindex] == Some(index) => Some(nindex),
+                    _ => None,
+                }
+            })
+        };
+
+        for (index, cell) in (start..).zip(next.iter_mut()) {
+            let from = match targets[index] {
+                Some(target) if targets[target].is_none() && winner(target) == Some(index) => target,
+                Some(_) => index,
+                None => winner(index).unwrap_or(index),
+            };
+
+            let settled = moved[from].clone();
+            if settled.differs(cell) {
+                changed.push(index);
+            }
+
+            *cell = settled;
+        }
+    }
 }
 
 impl<C> AutomataTrait for Automata<C>
-where C: 'static + Cell + Send + Sync + Default + PartialEq + Eq,
+where C: 'static + Cell + Send + Sync + Clone + Default + PartialEq,
       C::Params: 'static + Send + Sync
 {
     fn next(&mut self) {
-        let range_sender_iter = self.chunks.iter()
-            .zip(self.item_senders.iter().cycle());
-        let (result_send, result_recv) = channel();
-        for (range, sender) in range_sender_iter {
-            let item = WorkerItem {
-                current: Arc::clone(&self.current),
-                range: Range::clone(&range),
-                result_send: Sender::clone(&result_send),
-            };
+        // cells with random rules are seeded from one number per generation
+        let seed = self.rng.next_u64();
+        let mut changed = match self.mode {
+            UpdateMode::Neighbors(next) => self.next_cells(seed, move |context, item, changed| {
+                Automata::calculate_neighbors(context, next, item, changed);
+            }),
+            UpdateMode::Topple { next, topples, add_grain, relax } => {
+                let mut changed = Vec::new();
+                let before = relax.then(|| self.current.clone());
+                if relax {
+                    // start the next avalanche on a random cell
+                    let index = self.rng.gen_below(self.current.len());
+                    self.current[index] = add_grain(&self.current[index]);
+                }
+
+                // relaxing cells keep going until nothing topples, counting
+                // every toppling along the way
+                let passes = if relax { RELAX_PASSES } else { 1 };
+                self.topplings = 0;
+                for _ in 0..passes {
+                    let toppling = self.current.iter().filter(|cell| topples(cell)).count();
+                    if relax && toppling == 0 {
+                        break;
+                    }
+
+                    self.topplings += toppling;
+                    changed.extend(self.next_cells(seed, move |context, item, changed| {
+                        Automata::calculate_neighbors(context, next, item, changed);
+                    }));
+                }
 
-            // SAFETY: receiver is not dropped until the sender is dropped
-            sender.send(item).unwrap();
+                // cells can change in many passes, so compare them with the
+                // generation before to count each of them once
+                match before {
+                    Some(before) => vec![
+                        before.iter()
+                            .zip(self.current.iter())
+                            .enumerate()
+                            .filter(|(_, (before, current))| current.differs(before))
+                            .map(|(index, _)| index)
+                            .collect(),
+                    ],
+                    None => changed,
+                }
+            },
+            UpdateMode::Counts { weight, next } => {
+                if let Some(sums) = &mut self.sums {
+                    sums.update(self.grid_size, self.boundary, &self.current, weight);
+                }
+
+                self.next_cells(seed, move |context, item, changed| {
+                    Automata::calculate_counts(context, next, item, changed);
+                })
+            },
+            UpdateMode::Blocks(next_block) => self.next_blocks(seed, next_block),
+            UpdateMode::Moves(next_move) => self.next_moves(seed, next_move),
+            // agent cells never change by themselves
+            UpdateMode::Agents => Vec::new(),
+        };
+
+        // then move the agents over the new generation, redrawing the cells
+        // they leave
+        let mut agent_cells = Vec::new();
+        if let Some(agents) = &mut self.agents {
+            let rows = self.grid_size.rows;
+            agent_cells.extend(agents.agents().iter().map(|agent| agent.col * rows + agent.row));
+
+            let mut agent_changed = Vec::new();
+            agents.step(self.grid_size, self.boundary, &self.cell_params, &mut self.current, &mut agent_changed);
+            changed.push(agent_changed);
         }
 
-        // drop the extra sender used to clone
-        drop(result_send);
+        self.changed = changed.iter().map(Vec::len).sum();
+        self.generation += 1;
 
-        // receive results from worker threads
-        while let Ok((index, cell)) = result_recv.recv() {
-            let next_cell = &mut self.next[index];
-            *next_cell = cell;
+        // update the renderer for cells that changed
+        if let Some(renderer) = &mut self.renderer {
+            for index in changed.into_iter().flatten().chain(agent_cells) {
+                let (col, row) = linear_to_grid(self.grid_size.rows, index);
+                let color = self.current[index].color(&self.cell_params);
+                renderer.set_pixel(col, row, color);
+            }
 
-            // if the cell is different from last generation, update the texture
-            let curr_cell = &self.current[index];
-            if self.texture.first_update || *next_cell != *curr_cell {
-                let (x, y) = linear_to_grid(self.grid_size.rows, index);
-                let color = next_cell.color(&self.cell_params);
-                self.texture.set_pixel(x, y, color);
+            for agent in self.agents.iter().flat_map(Agents::agents) {
+                renderer.set_pixel(agent.col, agent.row, AGENT_COLOR);
             }
         }
+    }
+
+    fn render(&mut self) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.draw();
+        }
+    }
 
-        // SAFETY: results are back from worker threads and additional
-        // references were dropped
-        let current = Arc::get_mut(&mut self.current).unwrap();
-        std::mem::swap(current, &mut self.next);
+    fn reset(&mut self) {
+        self.current = C::initial(&self.cell_params, self.grid_size, &mut self.rng).into_boxed_slice();
 
-        // update complete
-        self.texture.first_update = false;
+        self.changed = self.current.len();
+        self.topplings = 0;
+        self.generation = 0;
+        if let Some(agents) = &mut self.agents {
+            agents.reset();
+        }
+
+        self.redraw();
     }
 
-    fn render(&self) {
-        self.texture.update();
-        self.texture.fill_screen();
+    fn load_pattern(&mut self, pattern: &Pattern, offset: Option<(usize, usize)>) -> Result<(), PatternError> {
+        let GridSize { cols, rows } = self.grid_size;
+        if pattern.width > cols || pattern.height > rows {
+            return Err(PatternError {
+                message: "pattern does not fit in the grid",
+            });
+        }
+
+        // center the pattern unless given an offset
+        let (col_offset, row_offset) = offset.unwrap_or((
+            (cols - pattern.width) / 2,
+            (rows - pattern.height) / 2,
+        ));
+
+        if col_offset + pattern.width > cols || row_offset + pattern.height > rows {
+            return Err(PatternError {
+                message: "pattern does not fit in the grid at the given offset",
+            });
+        }
+
+        let mut current = vec![C::default(); cols * rows].into_boxed_slice();
+        for (col, row, state) in pattern.cells() {
+            let cell = C::from_state(state, &self.cell_params).ok_or(PatternError {
+                message: "pattern contains a state this cell type does not have",
+            })?;
+
+            current[(col + col_offset) * rows + row + row_offset] = cell;
+        }
+
+        self.current = current;
+        self.changed = self.current.len();
+        self.topplings = 0;
+        self.generation = 0;
+        if let Some(agents) = &mut self.agents {
+            agents.reset();
+        }
+
+        self.redraw();
+
+        Ok(())
     }
-}
 
-impl AutomataTexture {
-    fn update(&self) {
-        self.texture.update(&self.image);
+    fn to_pattern(&self, region: Option<Region>) -> Pattern {
+        let GridSize { cols, rows } = self.grid_size;
+        let Region { col, row, width, height } = region.unwrap_or(Region {
+            col: 0,
+            row: 0,
+            width: cols,
+            height: rows,
+        });
+
+        // clip the region to the grid
+        let width = width.min(cols.saturating_sub(col));
+        let height = height.min(rows.saturating_sub(row));
+
+        let runs = (0..width)
+            .flat_map(|dcol| (0..height).map(move |drow| (dcol, drow)))
+            .filter_map(|(dcol, drow)| {
+                let state = self.current[(col + dcol) * rows + row + drow].state();
+                (state != 0).then_some((dcol, drow, 1, state))
+            })
+            .collect();
+
+        Pattern {
+            width,
+            height,
+            rule: C::rule(&self.cell_params),
+            runs,
+        }
     }
 
-    fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
-        self.image.set_pixel(x as u32, y as u32, color);
+    fn save_snapshot(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        snapshot::write_header(&mut writer, C::NAME);
+
+        writer.u64(self.grid_size.cols as u64);
+        writer.u64(self.grid_size.rows as u64);
+        writer.u64(self.generation);
+        writer.u64(self.rng.state());
+        writer.u8(snapshot::boundary_to_u8(self.boundary));
+
+        writer.u64(self.offsets.len() as u64);
+        for &(dcol, drow) in self.offsets.iter() {
+            writer.i64(dcol as i64);
+            writer.i64(drow as i64);
+        }
+
+        C::write_params(&self.cell_params, &mut writer);
+        for cell in self.current.iter() {
+            cell.write(&mut writer);
+        }
+
+        match &self.agents {
+            Some(agents) => {
+                writer.u8(1);
+                agents.write(&mut writer);
+            },
+            None => writer.u8(0),
+        }
+
+        writer.into_bytes()
+    }
+
+    fn attach_renderer(&mut self, renderer: Box<dyn Renderer>) {
+        self.renderer = Some(renderer);
+        self.redraw();
     }
 
-    fn fill_screen(&self) {
-        draw_texture_ex(
-            &self.texture,
-            0.0,
-            0.0,
-            WHITE,
-            self.params.clone()
-        );
+    fn grid_size(&self) -> GridSize {
+        self.grid_size
+    }
+
+    fn changed(&self) -> usize {
+        self.changed
+    }
+
+    fn topplings(&self) -> Option<usize> {
+        matches!(self.mode, UpdateMode::Topple { .. }).then_some(self.topplings)
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn color(&self, col: usize, row: usize) -> Color {
+        let agent_here = self.agents.iter()
+            .flat_map(Agents::agents)
+            .any(|agent| (agent.col, agent.row) == (col, row));
+
+        if agent_here {
+            return AGENT_COLOR;
+        }
+
+        let index = col * self.grid_size.rows + row;
+        self.current[index].color(&self.cell_params)
+    }
+
+    fn paint(&mut self, col: usize, row: usize, brush: Brush) {
+        let GridSize { cols, rows } = self.grid_size;
+        if col >= cols || row >= rows {
+            return;
+        }
+
+        let cell = match brush {
+            Brush::Paint => self.paint_states[self.paint_state].clone(),
+            Brush::Erase => C::default(),
+        };
+
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_pixel(col, row, cell.color(&self.cell_params));
+        }
+
+        self.current[col * rows + row] = cell;
+    }
+
+    fn next_paint_state(&mut self) {
+        self.paint_state = (self.paint_state + 1) % self.paint_states.len();
+    }
+
+    fn paint_color(&self) -> Color {
+        self.paint_states[self.paint_state].color(&self.cell_params)
+    }
+}
+
+fn redraw<C: Cell>(renderer: &mut dyn Renderer, grid_size: GridSize, cell_params: &C::Params, cells: &[C]) {
+    for (index, cell) in cells.iter().enumerate() {
+        let (col, row) = linear_to_grid(grid_size.rows, index);
+        renderer.set_pixel(col, row, cell.color(cell_params));
     }
 }
 
@@ -246,3 +885,325 @@ fn linear_to_grid(rows: usize, index: usize) -> (usize, usize) {
     let row = index % rows;
     (col, row)
 }
+
+/// Whether block cells would have blocks overlapping at the seam of a
+/// wrapping grid, which breaks rules that conserve or reverse their cells.
+fn overlapping_blocks<C: Cell>(grid_size: GridSize, boundary: Boundary, mode: &UpdateMode<C>) -> bool {
+    let odd = grid_size.cols % 2 == 1 || grid_size.rows % 2 == 1;
+    matches!(mode, UpdateMode::Blocks(_)) && boundary.wraps() && odd
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::cell::prelude::*;
+    use crate::cell::life::LifeRule;
+    use crate::color::BLACK;
+    use crate::neighborhood::Shape;
+
+    pub(crate) fn params(cols: usize, rows: usize) -> AutomataParams {
+        AutomataParams {
+            grid_size: GridSize { cols, rows },
+            threads: 3,
+            chunks: 7,
+            boundary: Boundary::Dead,
+            neighborhood: Neighborhood::Shape(Shape::Moore, 1),
+            seed: 1,
+        }
+    }
+
+    fn life(cols: usize, rows: usize) -> Automata<Life> {
+        Automata::new(params(cols, rows), LifeParams {
+            alive_ratio: 0.5,
+            rule: LifeRule::from_str("B3/S23").unwrap(),
+        })
+    }
+
+    /// Saves a snapshot of `automata` and resumes it with a different number
+    /// of threads and chunks.
+    fn resume<C>(automata: &Automata<C>) -> Automata<C>
+    where C: 'static + Cell + Send + Sync + Clone + Default + PartialEq,
+          C::Params: 'static + Send + Sync
+    {
+        let bytes = automata.save_snapshot();
+        let (name, mut reader) = snapshot::read_header(&bytes).unwrap();
+        assert_eq!(name, C::NAME);
+        Automata::from_snapshot(&mut reader, 2, 5).unwrap()
+    }
+
+    /// Runs `automata` alongside a copy resumed from a snapshot, checking
+    /// that both give the same generations.
+    pub(crate) fn check_resume<C>(mut automata: Automata<C>, generations: usize)
+    where C: 'static + Cell + Send + Sync + Clone + Default + PartialEq,
+          C::Params: 'static + Send + Sync
+    {
+        for _ in 0..generations {
+            automata.next();
+        }
+
+        let mut resumed = resume(&automata);
+        assert_eq!(resumed.save_snapshot(), automata.save_snapshot());
+        for _ in 0..generations {
+            automata.next();
+            resumed.next();
+            assert_eq!(resumed.changed(), automata.changed());
+        }
+
+        assert_eq!(resumed.generation(), automata.generation());
+        assert_eq!(resumed.save_snapshot(), automata.save_snapshot());
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        check_resume(life(40, 30), 10);
+
+        let cyclic = Automata::<Cyclic>::new(params(40, 30), CyclicParams {
+            palette: fade(5),
+            threshold: 1,
+        });
+
+        check_resume(cyclic, 10);
+    }
+
+    #[test]
+    fn chunks_and_threads_do_not_change_the_run() {
+        // including more chunks than cells, which leaves most of them empty
+        let splits = [(1, 1), (3, 7), (4, 64), (2, 2000)];
+        let snapshots: Vec<Vec<u8>> = splits.iter()
+            .map(|&(threads, chunks)| {
+                let mut automata = Automata::<Life>::new(AutomataParams { threads, chunks, ..params(40, 30) }, LifeParams {
+                    alive_ratio: 0.5,
+                    rule: LifeRule::from_str("B3/S23").unwrap(),
+                });
+
+                for _ in 0..10 {
+                    automata.next();
+                }
+
+                automata.save_snapshot()
+            })
+            .collect();
+
+        assert!(snapshots.iter().all(|snapshot| *snapshot == snapshots[0]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn needs_a_thread_and_a_chunk() {
+        Automata::<Life>::new(AutomataParams { threads: 0, ..params(10, 10) }, LifeParams {
+            alive_ratio: 0.5,
+            rule: LifeRule::from_str("B3/S23").unwrap(),
+        });
+    }
+
+    #[test]
+    fn counts_every_changed_cell_once() {
+        fn check<C>(mut automata: Automata<C>)
+        where C: 'static + Cell + Send + Sync + Clone + Default + PartialEq,
+              C::Params: 'static + Send + Sync
+        {
+            for _ in 0..10 {
+                let previous = automata.current.clone();
+                automata.next();
+
+                let changed = previous.iter()
+                    .zip(automata.current.iter())
+                    .filter(|(previous, current)| previous != current)
+                    .count();
+
+                assert_eq!(automata.changed(), changed);
+            }
+        }
+
+        check(life(40, 30));
+
+        // the pile takes many passes to relax, with cells changing in several
+        // of them
+        let sandpile = Automata::<Sandpile>::new(params(21, 21), SandpileParams {
+            pile: 200,
+            relax: true,
+            palette: vec![BLACK; 5],
+        });
+
+        check(sandpile);
+    }
+
+    #[test]
+    fn update_modes_resume_the_same_run() {
+        for boundary in [Boundary::Dead, Boundary::Torus] {
+            let params = || AutomataParams { boundary, ..params(41, 37) };
+
+            let ltl = Automata::<Ltl>::new(params(), LtlParams {
+                alive_ratio: 0.5,
+                rule: "R3,C4,M1,S4..9,B5..8,NM".parse().unwrap(),
+            });
+
+            // blocks need an even grid to wrap
+            let margolus = Automata::<Margolus>::new(AutomataParams { boundary, ..self::params(42, 36) }, MargolusParams {
+                alive_ratio: 0.2,
+                rule: "bbm".parse().unwrap(),
+            });
+
+            let sand = Automata::<Sand>::new(params(), SandParams {
+                sand_ratio: 0.3,
+                water_ratio: 0.3,
+            });
+
+            check_resume(ltl, 10);
+            check_resume(margolus, 10);
+            check_resume(sand, 10);
+        }
+    }
+
+    #[test]
+    fn counts_match_neighbor_lookups() {
+        let boundaries = [Boundary::Dead, Boundary::Torus, Boundary::Klein, Boundary::Mirror, Boundary::Fixed];
+        for boundary in boundaries {
+            let params = || AutomataParams { boundary, ..params(23, 19) };
+            let mut life = Automata::<Life>::new(params(), LifeParams {
+                alive_ratio: 0.4,
+                rule: LifeRule::from_str("B3/S23").unwrap(),
+            });
+
+            let mut ltl = Automata::<Ltl>::new(params(), LtlParams {
+                alive_ratio: 0.4,
+                rule: "R1,C0,M0,S2..3,B3..3,NM".parse().unwrap(),
+            });
+
+            for _ in 0..10 {
+                life.next();
+                ltl.next();
+                let life_states: Vec<u8> = life.current.iter().map(Cell::state).collect();
+                let ltl_states: Vec<u8> = ltl.current.iter().map(Cell::state).collect();
+                assert_eq!(life_states, ltl_states);
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_change_together() {
+        // a lone billiard ball moves diagonally through every block it meets,
+        // shifted or not
+        let mut automata = Automata::<Margolus>::new(params(8, 8), MargolusParams {
+            alive_ratio: 0.0,
+            rule: "bbm".parse().unwrap(),
+        });
+
+        let ball = Pattern::from_str("x = 1, y = 1\no!").unwrap();
+        automata.load_pattern(&ball, Some((2, 2))).unwrap();
+        for generation in 1..=3 {
+            automata.next();
+            assert_eq!(automata.changed(), 2);
+
+            let pattern = automata.to_pattern(None);
+            assert_eq!(pattern.cells().collect::<Vec<_>>(), [(2 + generation, 2 + generation, 1)]);
+        }
+
+        // then waits in the corner, where its shifted block would go past the
+        // edge of the grid, before bouncing back
+        for _ in 0..2 {
+            automata.next();
+        }
+
+        automata.next();
+        assert_eq!(automata.changed(), 0);
+        assert_eq!(automata.to_pattern(None).cells().collect::<Vec<_>>(), [(7, 7, 1)]);
+
+        automata.next();
+        assert_eq!(automata.to_pattern(None).cells().collect::<Vec<_>>(), [(6, 6, 1)]);
+    }
+
+    #[test]
+    fn rejects_overlapping_blocks() {
+        let margolus = |cols, rows, boundary| Automata::<Margolus>::new(AutomataParams { boundary, ..params(cols, rows) }, MargolusParams {
+            alive_ratio: 0.2,
+            rule: "bbm".parse().unwrap(),
+        });
+
+        // an even grid resized to an odd one with the same number of cells
+        let header_len = 8 + 2 + 2 + Margolus::NAME.len();
+        let load = |bytes: &[u8], cols: u64, rows: u64| {
+            let mut bytes = bytes.to_vec();
+            bytes[header_len..header_len + 8].copy_from_slice(&cols.to_le_bytes());
+            bytes[header_len + 8..header_len + 16].copy_from_slice(&rows.to_le_bytes());
+            let (_, mut reader) = snapshot::read_header(&bytes).unwrap();
+            Automata::<Margolus>::from_snapshot(&mut reader, 1, 1).err().map(|err| err.message)
+        };
+
+        for boundary in [Boundary::Torus, Boundary::Klein] {
+            let bytes = margolus(4, 6, boundary).save_snapshot();
+            assert_eq!(load(&bytes, 4, 6), None);
+            assert_eq!(load(&bytes, 3, 8), Some("snapshot has blocks that overlap where the grid wraps"));
+            assert_eq!(load(&bytes, 8, 3), Some("snapshot has blocks that overlap where the grid wraps"));
+        }
+
+        // blocks that stop at the edge do not overlap
+        let bytes = margolus(4, 6, Boundary::Dead).save_snapshot();
+        assert_eq!(load(&bytes, 3, 8), None);
+
+        let result = std::panic::catch_unwind(|| margolus(5, 6, Boundary::Torus));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_corrupt_snapshots() {
+        let bytes = life(6, 4).save_snapshot();
+        let header_len = 8 + 2 + 2 + Life::NAME.len();
+        let load = |bytes: &[u8]| {
+            let (_, mut reader) = snapshot::read_header(bytes).unwrap();
+            Automata::<Life>::from_snapshot(&mut reader, 1, 1).err().map(|err| err.message)
+        };
+
+        assert_eq!(load(&bytes), None);
+
+        // truncated, or with extra bytes
+        assert!(load(&bytes[..bytes.len() - 1]).is_some());
+        assert!(load(&[&bytes[..], &[0]].concat()).is_some());
+
+        // sizes that overflow or are larger than the snapshot
+        for (cols, rows) in [(u64::MAX, 2u64), (1 << 32, 1 << 32), (6, 5), (0, 4)] {
+            let mut corrupt = bytes.clone();
+            corrupt[header_len..header_len + 8].copy_from_slice(&cols.to_le_bytes());
+            corrupt[header_len + 8..header_len + 16].copy_from_slice(&rows.to_le_bytes());
+            assert!(load(&corrupt).is_some(), "{cols}x{rows} should not load");
+        }
+    }
+
+    #[test]
+    fn rejects_empty_cyclic_palettes() {
+        let mut writer = Writer::new();
+        writer.u8(1);
+        writer.u16(0);
+        assert!(Cyclic::read_params(&mut Reader::new(&writer.into_bytes())).is_none());
+    }
+
+    #[test]
+    fn loads_patterns_centered() {
+        let mut automata = life(7, 5);
+        let glider = Pattern::from_str("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        automata.load_pattern(&glider, None).unwrap();
+
+        let pattern = automata.to_pattern(Some(Region { col: 2, row: 1, width: 3, height: 3 }));
+        assert_eq!(pattern.to_rle(), "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+        assert_eq!(automata.to_pattern(None).cells().count(), 5);
+    }
+
+    #[test]
+    fn rejects_patterns_that_do_not_fit() {
+        let mut automata = life(7, 5);
+        let wide = Pattern::from_str("x = 8, y = 1\no!").unwrap();
+        assert!(automata.load_pattern(&wide, None).is_err());
+
+        let glider = Pattern::from_str("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        assert!(automata.load_pattern(&glider, Some((5, 0))).is_err());
+    }
+
+    #[test]
+    fn rejects_states_the_cell_type_does_not_have() {
+        let mut automata = life(7, 5);
+        let pattern = Pattern::from_str("x = 2, y = 1\nAB!").unwrap();
+        let err = automata.load_pattern(&pattern, None).unwrap_err();
+        assert_eq!(err.message, "pattern contains a state this cell type does not have");
+    }
+}
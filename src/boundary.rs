@@ -0,0 +1,152 @@
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Boundary {
+    /// Neighbors off the edge of the grid are ignored
+    #[default]
+    Dead,
+    /// Opposite edges are joined together
+    Torus,
+    /// Left and right edges are joined, top and bottom are joined with a twist
+    Klein,
+    /// Edges reflect the cells just inside of them
+    Mirror,
+    /// Neighbors off the edge of the grid are the default cell state
+    Fixed,
+}
+
+pub enum Neighbor {
+    Index(usize),
+    Fixed,
+    Outside,
+}
+
+impl Boundary {
+    /// Whether every neighbor off the edge of the grid is a cell of the grid,
+    /// so that nothing passed over the edge is lost.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Boundary::Torus | Boundary::Klein | Boundary::Mirror)
+    }
+
+    /// Whether cells off one edge of the grid wrap around to the opposite
+    /// edge.
+    pub fn wraps(&self) -> bool {
+        matches!(self, Boundary::Torus | Boundary::Klein)
+    }
+
+    pub fn resolve(&self, cols: usize, rows: usize, col: isize, row: isize) -> Neighbor {
+        let (icols, irows) = (cols as isize, rows as isize);
+        if (0..icols).contains(&col) && (0..irows).contains(&row) {
+            return Neighbor::Index(col as usize * rows + row as usize);
+        }
+
+        let (col, row) = match *self {
+            Boundary::Dead => return Neighbor::Outside,
+            Boundary::Fixed => return Neighbor::Fixed,
+            Boundary::Torus => (col.rem_euclid(icols), row.rem_euclid(irows)),
+            Boundary::Klein => {
+                // every time the top or bottom edge is crossed, the grid is flipped
+                let col = match row.div_euclid(irows) % 2 {
+                    0 => col,
+                    _ => icols - 1 - col,
+                };
+
+                (col.rem_euclid(icols), row.rem_euclid(irows))
+            },
+            Boundary::Mirror => (reflect(col, icols), reflect(row, irows)),
+        };
+
+        Neighbor::Index(col as usize * rows + row as usize)
+    }
+}
+
+fn reflect(pos: isize, len: isize) -> isize {
+    let pos = pos.rem_euclid(2 * len);
+    if pos < len { pos } else { 2 * len - 1 - pos }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLS: usize = 4;
+    const ROWS: usize = 3;
+
+    /// The (column, row) a boundary maps an offset grid position to, or
+    /// `None` if it is not a cell of the grid.
+    fn at(boundary: Boundary, col: isize, row: isize) -> Option<(usize, usize)> {
+        match boundary.resolve(COLS, ROWS, col, row) {
+            Neighbor::Index(index) => Some((index / ROWS, index % ROWS)),
+            Neighbor::Fixed | Neighbor::Outside => None,
+        }
+    }
+
+    /// Positions just off each edge and corner of the grid.
+    const OFF_GRID: [(isize, isize); 10] = [
+        (-1, 0), (4, 0), (1, -1), (1, 3),
+        (-1, -1), (4, -1), (-1, 3), (4, 3),
+        (-4, 1), (1, 6),
+    ];
+
+    #[test]
+    fn cells_inside_the_grid_are_the_same_for_every_boundary() {
+        for boundary in [Boundary::Dead, Boundary::Torus, Boundary::Klein, Boundary::Mirror, Boundary::Fixed] {
+            for col in 0..COLS {
+                for row in 0..ROWS {
+                    assert_eq!(at(boundary, col as isize, row as isize), Some((col, row)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dead_and_fixed_edges_have_no_cells() {
+        for (col, row) in OFF_GRID {
+            assert!(matches!(Boundary::Dead.resolve(COLS, ROWS, col, row), Neighbor::Outside));
+            assert!(matches!(Boundary::Fixed.resolve(COLS, ROWS, col, row), Neighbor::Fixed));
+        }
+    }
+
+    #[test]
+    fn torus_edges_wrap_around() {
+        let expected = [
+            (3, 0), (0, 0), (1, 2), (1, 0),
+            (3, 2), (0, 2), (3, 0), (0, 0),
+            (0, 1), (1, 0),
+        ];
+
+        for ((col, row), expected) in OFF_GRID.into_iter().zip(expected) {
+            assert_eq!(at(Boundary::Torus, col, row), Some(expected), "({col}, {row})");
+        }
+    }
+
+    #[test]
+    fn klein_edges_flip_across_the_top_and_bottom() {
+        // the left and right edges wrap as on a torus, while crossing the top
+        // or bottom edge also flips the column, and crossing both flips back
+        let expected = [
+            (3, 0), (0, 0), (2, 2), (2, 0),
+            (0, 2), (3, 2), (0, 0), (3, 0),
+            (0, 1), (1, 0),
+        ];
+
+        for ((col, row), expected) in OFF_GRID.into_iter().zip(expected) {
+            assert_eq!(at(Boundary::Klein, col, row), Some(expected), "({col}, {row})");
+        }
+    }
+
+    #[test]
+    fn mirror_edges_reflect_the_cells_inside() {
+        let expected = [
+            (0, 0), (3, 0), (1, 0), (1, 2),
+            (0, 0), (3, 0), (0, 2), (3, 2),
+            (3, 1), (1, 0),
+        ];
+
+        for ((col, row), expected) in OFF_GRID.into_iter().zip(expected) {
+            assert_eq!(at(Boundary::Mirror, col, row), Some(expected), "({col}, {row})");
+        }
+    }
+}
@@ -1,9 +1,11 @@
-use macroquad::prelude::*;
-use macroquad::rand::gen_range;
-use crate::cell::Cell;
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub enum Brain {
+    #[default]
     Dead,
     Dying,
     Alive
@@ -13,24 +15,8 @@ pub struct BrainParams {
     pub alive_ratio: f32,
 }
 
-impl Cell for Brain {
-    type Params = BrainParams;
-
-    fn new(params: &Self::Params) -> Self {
-        if gen_range(0.0, 1.0) < params.alive_ratio {
-            if gen_range(0.0, 1.0) > 0.5 {
-                Brain::Alive
-            }
-            else {
-                Brain::Dying
-            }
-        }
-        else {
-            Brain::Dead
-        }
-    }
-
-    fn next<'a>(&'a self, _params: &Self::Params, neighbors: impl IntoIterator<Item = &'a Self>) -> Self {
+impl Brain {
+    fn next<'a>(&'a self, _params: &BrainParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
         match *self {
             Brain::Dead => {
                 let count = neighbors.into_iter()
@@ -46,6 +32,25 @@ impl Cell for Brain {
             Brain::Alive => Brain::Dying,
         }
     }
+}
+
+impl Cell for Brain {
+    type Params = BrainParams;
+    const NAME: &'static str = "brain";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        if rng.gen_bool(params.alive_ratio) {
+            if rng.gen_bool(0.5) {
+                Brain::Alive
+            }
+            else {
+                Brain::Dying
+            }
+        }
+        else {
+            Brain::Dead
+        }
+    }
 
     fn color(&self, _params: &Self::Params) -> Color {
         match *self {
@@ -54,10 +59,43 @@ impl Cell for Brain {
             Brain::Alive => WHITE
         }
     }
-}
 
-impl Default for Brain {
-    fn default() -> Self {
-        Brain::Dead
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(Brain::next)
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        vec![Brain::Alive, Brain::Dying]
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        match state {
+            0 => Some(Brain::Dead),
+            1 => Some(Brain::Alive),
+            2 => Some(Brain::Dying),
+            _ => None,
+        }
+    }
+
+    fn state(&self) -> u8 {
+        match *self {
+            Brain::Dead => 0,
+            Brain::Alive => 1,
+            Brain::Dying => 2,
+        }
+    }
+
+    fn rule(_params: &Self::Params) -> Option<String> {
+        Some("/2/3".to_owned())
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.alive_ratio);
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        Some(BrainParams {
+            alive_ratio: reader.f32()?,
+        })
     }
 }
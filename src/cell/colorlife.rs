@@ -0,0 +1,192 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::cell::life::{self, LifeRule};
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// The colors of live cells, in order.
+const COLORS: [Color; 4] = [RED, BLUE, GREEN, YELLOW];
+
+/// A cell in a Life-like automata where live cells have one of several
+/// colors, like Immigration (2 colors) and QuadLife (4 colors). Births and
+/// survivals follow a `LifeRule`, and newborn cells take the color most of
+/// their parents have.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ColorLife {
+    /// 0 for dead cells, or 1 plus the color of a live cell
+    state: u8,
+}
+
+pub struct ColorLifeParams {
+    pub alive_ratio: f32,
+    pub rule: LifeRule,
+    /// The number of colors, from 2 through 4
+    pub colors: u8,
+}
+
+impl ColorLife {
+    fn color_index(&self) -> Option<usize> {
+        self.state.checked_sub(1).map(usize::from)
+    }
+
+    /// The color of a newborn cell from how many of its parents have each
+    /// color. When every parent has a different color and there is a color
+    /// none of them have, as with 3 parents in QuadLife, the newborn takes
+    /// that color. Other ties go to the first of the tied colors.
+    fn born(params: &ColorLifeParams, counts: [usize; 4]) -> ColorLife {
+        let counts = &counts[..params.colors as usize];
+        let most = counts.iter().copied().max().unwrap_or(0);
+        let color = match counts.iter().position(|&count| count == 0) {
+            Some(missing) if most == 1 && counts.iter().filter(|&&count| count == 1).count() > 1 => missing,
+            // SAFETY: `most` is one of the counts
+            _ => counts.iter().position(|&count| count == most).unwrap(),
+        };
+
+        ColorLife {
+            state: color as u8 + 1,
+        }
+    }
+
+    fn next<'a>(&'a self, params: &ColorLifeParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        let rule = &params.rule;
+        let counts = life::tally(neighbors, ColorLife::color_index);
+        let alive = self.state != 0;
+        let next_alive = if rule.is_totalistic() {
+            let count = counts.iter().sum();
+            match alive {
+                false => rule.is_birth(count),
+                true => rule.is_survival(count),
+            }
+        }
+        else {
+            // non-totalistic rules depend on where the live neighbors are
+            let mask = life::arrangement(neighbors, |neighbor| neighbor.state != 0);
+            match alive {
+                false => rule.is_birth_arrangement(mask),
+                true => rule.is_survival_arrangement(mask),
+            }
+        };
+
+        match (alive, next_alive) {
+            (_, false) => ColorLife::default(),
+            (true, true) => self.clone(),
+            (false, true) => ColorLife::born(params, counts),
+        }
+    }
+}
+
+impl Cell for ColorLife {
+    type Params = ColorLifeParams;
+    const NAME: &'static str = "colorlife";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        match rng.gen_bool(params.alive_ratio) {
+            true => ColorLife {
+                state: rng.gen_below(params.colors as usize) as u8 + 1,
+            },
+            false => ColorLife::default(),
+        }
+    }
+
+    fn color(&self, _params: &Self::Params) -> Color {
+        match self.color_index() {
+            Some(color) => COLORS[color],
+            None => WHITE,
+        }
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(ColorLife::next)
+    }
+
+    fn paint_states(params: &Self::Params) -> Vec<Self> {
+        (1..=params.colors)
+            .map(|state| ColorLife { state })
+            .collect()
+    }
+
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> {
+        (state <= params.colors).then_some(ColorLife { state })
+    }
+
+    fn state(&self) -> u8 {
+        self.state
+    }
+
+    fn rule(params: &Self::Params) -> Option<String> {
+        Some(params.rule.to_string())
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.alive_ratio);
+        params.rule.write(writer);
+        writer.u8(params.colors);
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        Some(ColorLifeParams {
+            alive_ratio: reader.f32()?,
+            rule: LifeRule::read(reader)?,
+            colors: reader.u8().filter(|colors| (2..=4).contains(colors))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::neighborhood::{Neighborhood, Shape};
+
+    fn params(colors: u8) -> ColorLifeParams {
+        ColorLifeParams {
+            alive_ratio: 0.5,
+            rule: LifeRule::from_str("B3/S23").unwrap(),
+            colors,
+        }
+    }
+
+    /// The next state of a cell in `state` with neighbors in `states`.
+    fn next(params: &ColorLifeParams, state: u8, states: &[u8]) -> u8 {
+        let offsets = Neighborhood::Shape(Shape::Moore, 1).offsets();
+        let cells: Vec<ColorLife> = states.iter()
+            .map(|&state| ColorLife { state })
+            .collect();
+
+        let mut neighbors: Vec<Option<&ColorLife>> = vec![None; offsets.len()];
+        for (neighbor, cell) in neighbors.iter_mut().zip(cells.iter()) {
+            *neighbor = Some(cell);
+        }
+
+        let cell = ColorLife { state };
+        cell.next(params, Neighbors::new(&offsets, &neighbors), &mut Rng::new(0)).state
+    }
+
+    #[test]
+    fn immigration_births_take_the_majority_color() {
+        let immigration = params(2);
+        assert_eq!(next(&immigration, 0, &[1, 1, 2]), 1);
+        assert_eq!(next(&immigration, 0, &[2, 1, 2]), 2);
+        assert_eq!(next(&immigration, 0, &[2, 2, 2]), 2);
+    }
+
+    #[test]
+    fn quadlife_births_with_three_colors_take_the_fourth() {
+        let quadlife = params(4);
+        assert_eq!(next(&quadlife, 0, &[1, 2, 3]), 4);
+        assert_eq!(next(&quadlife, 0, &[4, 1, 2]), 3);
+        assert_eq!(next(&quadlife, 0, &[2, 3, 4]), 1);
+
+        // otherwise the majority still wins
+        assert_eq!(next(&quadlife, 0, &[3, 4, 3]), 3);
+    }
+
+    #[test]
+    fn survivors_keep_their_color() {
+        let quadlife = params(4);
+        assert_eq!(next(&quadlife, 2, &[1, 1]), 2);
+        assert_eq!(next(&quadlife, 3, &[4, 4, 4]), 3);
+        assert_eq!(next(&quadlife, 3, &[4]), 0);
+        assert_eq!(next(&quadlife, 0, &[1, 1]), 0);
+    }
+}
@@ -1,66 +1,124 @@
-use macroquad::color::Color;
-use macroquad::rand::gen_range;
-use crate::cell::Cell;
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::Color;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
 
 pub mod palette {
     use std::sync::LazyLock;
-    use macroquad::prelude::*;
+    use crate::color::*;
 
-    pub static COLORS: &'static [Color] = &[PINK, RED, ORANGE, YELLOW, GREEN, BLUE, VIOLET, MAGENTA];
+    pub static COLORS: &[Color] = &[PINK, RED, ORANGE, YELLOW, GREEN, BLUE, VIOLET, MAGENTA];
     pub static GRAYSCALE: LazyLock<Vec<Color>> = LazyLock::new(|| {
         (1..=18)
-            .into_iter()
             .map(|i| {
                 let val = 1.0 / i as f32;
                 Color::new(val, val, val, 1.0)
             })
             .collect()
     });
+
+    /// Fades from white to black over the given number of colors.
+    pub fn fade(colors: usize) -> Vec<Color> {
+        (0..colors)
+            .map(|color| WHITE.lerp(BLACK, color as f32 / (colors - 1).max(1) as f32))
+            .collect()
+    }
+
+    /// Starts with white, then cycles through the given colors.
+    pub fn cycle(colors: usize, cycle: &[Color]) -> Vec<Color> {
+        (0..colors)
+            .map(|color| match color {
+                0 => WHITE,
+                _ => cycle[(color - 1) % cycle.len()],
+            })
+            .collect()
+    }
+
+    /// Blends evenly through the given colors over the given number of
+    /// colors.
+    pub fn blend(colors: usize, stops: &[Color]) -> Vec<Color> {
+        (0..colors)
+            .map(|color| Color::gradient(stops, color as f32 / (colors - 1).max(1) as f32))
+            .collect()
+    }
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub struct Cyclic {
     value: usize,
 }
 
 pub struct CyclicParams {
-    pub palette: &'static [Color],
+    pub palette: Vec<Color>,
     pub threshold: u8,
 }
 
-impl Cell for Cyclic {
-    type Params = CyclicParams;
+impl Cyclic {
+    fn next<'a>(&'a self, params: &CyclicParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        let next_value = (self.value + 1) % params.palette.len();
+        let count = neighbors
+            .into_iter()
+            .filter(|neighbor| next_value == neighbor.value)
+            .count();
 
-    fn new(params: &Self::Params) -> Self {
-        let bin: f32 = 1.0 / params.palette.len() as f32;
-        let value = (gen_range(0.0, 1.0) / bin).floor() as usize;
+        let value = if count >= params.threshold as usize { next_value } else { self.value };
         Cyclic {
             value,
         }
     }
+}
 
-    fn next<'a>(&'a self, params: &Self::Params, neighbors: impl IntoIterator<Item = &'a Self>) -> Self {
-        let next_value = (self.value + 1) % params.palette.len();
-        let count = neighbors
-            .into_iter()
-            .map(|neighbor| if next_value == neighbor.value { 1 } else { 0 })
-            .sum::<u8>();
+impl Cell for Cyclic {
+    type Params = CyclicParams;
+    const NAME: &'static str = "cyclic";
 
-        let value = if count >= params.threshold { next_value } else { self.value };
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
         Cyclic {
-            value,
+            value: rng.gen_below(params.palette.len()),
         }
     }
 
     fn color(&self, params: &Self::Params) -> Color {
         params.palette[self.value]
     }
-}
 
-impl Default for Cyclic {
-    fn default() -> Self {
-        Cyclic {
-            value: 0,
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(Cyclic::next)
+    }
+
+    fn paint_states(params: &Self::Params) -> Vec<Self> {
+        (0..params.palette.len())
+            .map(|value| Cyclic { value })
+            .collect()
+    }
+
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> {
+        let value = state as usize;
+        (value < params.palette.len()).then_some(Cyclic { value })
+    }
+
+    fn state(&self) -> u8 {
+        self.value as u8
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.u8(params.threshold);
+        writer.u16(params.palette.len() as u16);
+        for color in params.palette.iter() {
+            for channel in [color.r, color.g, color.b, color.a] {
+                writer.f32(channel);
+            }
         }
     }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        let threshold = reader.u8()?;
+        let len = reader.u16()? as usize;
+        let palette = (0..len)
+            .map(|_| Some(Color::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?)))
+            .collect::<Option<Vec<Color>>>()
+            .filter(|palette| !palette.is_empty())?;
+
+        Some(CyclicParams { threshold, palette })
+    }
 }
@@ -0,0 +1,142 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A cell in the Drossel-Schwabl forest fire model.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub enum Forest {
+    #[default]
+    Empty,
+    Tree,
+    Burning,
+}
+
+pub struct ForestParams {
+    pub tree_ratio: f32,
+    /// The chance of a tree growing in an empty cell each generation
+    pub growth: f32,
+    /// The chance of lightning setting a tree on fire each generation
+    pub lightning: f32,
+}
+
+impl Forest {
+    fn next<'a>(&'a self, params: &ForestParams, neighbors: Neighbors<'a, Self>, rng: &mut Rng) -> Self {
+        match *self {
+            Forest::Empty if rng.gen_bool(params.growth) => Forest::Tree,
+            Forest::Empty => Forest::Empty,
+            Forest::Tree => {
+                let on_fire = neighbors.into_iter().any(|neighbor| *neighbor == Forest::Burning);
+                match on_fire || rng.gen_bool(params.lightning) {
+                    true => Forest::Burning,
+                    false => Forest::Tree,
+                }
+            },
+            Forest::Burning => Forest::Empty,
+        }
+    }
+}
+
+impl Cell for Forest {
+    type Params = ForestParams;
+    const NAME: &'static str = "forest";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        match rng.gen_bool(params.tree_ratio) {
+            true => Forest::Tree,
+            false => Forest::Empty,
+        }
+    }
+
+    fn color(&self, _params: &Self::Params) -> Color {
+        match *self {
+            Forest::Empty => BLACK,
+            Forest::Tree => DARKGREEN,
+            Forest::Burning => ORANGE,
+        }
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(Forest::next)
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        vec![Forest::Tree, Forest::Burning]
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        match state {
+            0 => Some(Forest::Empty),
+            1 => Some(Forest::Tree),
+            2 => Some(Forest::Burning),
+            _ => None,
+        }
+    }
+
+    fn state(&self) -> u8 {
+        match *self {
+            Forest::Empty => 0,
+            Forest::Tree => 1,
+            Forest::Burning => 2,
+        }
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.tree_ratio);
+        writer.f32(params.growth);
+        writer.f32(params.lightning);
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        Some(ForestParams {
+            tree_ratio: reader.f32()?,
+            growth: reader.f32()?,
+            lightning: reader.f32()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::{Automata, AutomataParams, AutomataTrait};
+    use crate::automata::tests::{check_resume, params};
+
+    fn forest(params: AutomataParams) -> Automata<Forest> {
+        Automata::new(params, ForestParams {
+            tree_ratio: 0.5,
+            growth: 0.05,
+            lightning: 0.01,
+        })
+    }
+
+    #[test]
+    fn random_rules_do_not_depend_on_the_split() {
+        let mut single = forest(AutomataParams { threads: 1, chunks: 1, ..params(40, 30) });
+        let mut split = forest(params(40, 30));
+        for _ in 0..20 {
+            single.next();
+            split.next();
+            assert_eq!(single.changed(), split.changed());
+        }
+
+        assert_eq!(single.save_snapshot(), split.save_snapshot());
+    }
+
+    #[test]
+    fn seeds_give_different_runs() {
+        let mut first = forest(params(40, 30));
+        let mut second = forest(AutomataParams { seed: 2, ..params(40, 30) });
+        for _ in 0..5 {
+            first.next();
+            second.next();
+        }
+
+        assert_ne!(first.to_pattern(None).to_rle(), second.to_pattern(None).to_rle());
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        check_resume(forest(params(40, 30)), 20);
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::cell::life::{self, LifeRule, RuleParseError, RuleParseReason};
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A cell in a Generations automata: 0 is dead, 1 is alive, and every state
+/// after that is dying, counting up until the cell is dead again.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Generations {
+    state: u8,
+}
+
+/// A Life rule with a number of states, where cells that do not survive
+/// decay through the extra states before dying.
+#[derive(Clone, Debug)]
+pub struct GenerationsRule {
+    pub life: LifeRule,
+    pub states: u8,
+}
+
+impl FromStr for GenerationsRule {
+    type Err = RuleParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the number of states comes last, e.g. "B2/S/C3" or "/2/3"
+        let (life, states) = s.rsplit_once('/').ok_or(RuleParseError {
+            message: "generations rule must end with the number of states",
+            reason: RuleParseReason::Format
+        })?;
+
+        let states = states.strip_prefix(['C', 'c']).unwrap_or(states);
+        let states = match states.parse::<u8>() {
+            Ok(states) if states >= 2 => states,
+            _ => return Err(RuleParseError {
+                message: "number of states must be from 2 through 255",
+                reason: RuleParseReason::DigitParse
+            }),
+        };
+
+        Ok(GenerationsRule {
+            life: LifeRule::from_str(life)?,
+            states,
+        })
+    }
+}
+
+impl fmt::Display for GenerationsRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/C{}", self.life, self.states)
+    }
+}
+
+pub struct GenerationsParams {
+    pub alive_ratio: f32,
+    pub rule: GenerationsRule,
+}
+
+impl Generations {
+    fn next<'a>(&'a self, params: &GenerationsParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        let rule = &params.rule;
+        let is_alive = |neighbor: &Generations| neighbor.state == 1;
+        let state = match self.state {
+            0 => {
+                let birth = match rule.life.is_totalistic() {
+                    true => rule.life.is_birth(neighbors.into_iter().filter(|n| is_alive(n)).count()),
+                    false => rule.life.is_birth_arrangement(life::arrangement(neighbors, is_alive)),
+                };
+
+                if birth { 1 } else { 0 }
+            },
+            1 => {
+                let survival = match rule.life.is_totalistic() {
+                    true => rule.life.is_survival(neighbors.into_iter().filter(|n| is_alive(n)).count()),
+                    false => rule.life.is_survival_arrangement(life::arrangement(neighbors, is_alive)),
+                };
+
+                if survival { 1 } else { 2 % rule.states }
+            },
+            state => (state + 1) % rule.states,
+        };
+
+        Generations { state }
+    }
+}
+
+impl Cell for Generations {
+    type Params = GenerationsParams;
+    const NAME: &'static str = "generations";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        // live cells start in any of the living or dying states
+        let state = match rng.gen_bool(params.alive_ratio) {
+            true => 1 + rng.gen_below(params.rule.states as usize - 1) as u8,
+            false => 0,
+        };
+
+        Generations { state }
+    }
+
+    fn color(&self, params: &Self::Params) -> Color {
+        match self.state {
+            0 => BLACK,
+            1 => WHITE,
+            state => {
+                // fade from blue towards black as the cell decays
+                let t = (state - 2) as f32 / (params.rule.states - 2) as f32;
+                BLUE.lerp(BLACK, t)
+            },
+        }
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(Generations::next)
+    }
+
+    fn paint_states(params: &Self::Params) -> Vec<Self> {
+        (1..params.rule.states)
+            .map(|state| Generations { state })
+            .collect()
+    }
+
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> {
+        (state < params.rule.states).then_some(Generations { state })
+    }
+
+    fn state(&self) -> u8 {
+        self.state
+    }
+
+    fn rule(params: &Self::Params) -> Option<String> {
+        Some(params.rule.to_string())
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.alive_ratio);
+        params.rule.life.write(writer);
+        writer.u8(params.rule.states);
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        Some(GenerationsParams {
+            alive_ratio: reader.f32()?,
+            rule: GenerationsRule {
+                life: LifeRule::read(reader)?,
+                states: reader.u8().filter(|&states| states >= 2)?,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::Automata;
+    use crate::automata::tests::{check_resume, params};
+    use crate::neighborhood::{Neighborhood, Shape};
+
+    fn rule(s: &str) -> GenerationsRule {
+        GenerationsRule::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn parses_bsc_and_sbc_notation() {
+        for s in ["B2/S/C3", "b2/s/c3", "/2/3", "B2/S/3"] {
+            let brain = rule(s);
+            assert_eq!(brain.states, 3);
+            assert!(brain.life.is_birth(2));
+            assert!((0..=8).all(|count| !brain.life.is_survival(count)));
+            assert_eq!(brain.to_string(), "B2/S/C3");
+        }
+
+        let star_wars = rule("345/2/4");
+        assert_eq!(star_wars.states, 4);
+        assert!(star_wars.life.is_birth(2) && !star_wars.life.is_birth(3));
+        assert!([3, 4, 5].iter().all(|&count| star_wars.life.is_survival(count)));
+        assert_eq!(star_wars.to_string(), "B2/S345/C4");
+        assert_eq!(rule(&star_wars.to_string()).to_string(), "B2/S345/C4");
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        for s in ["", "B2/S", "B2/S/C1", "B2/S/C256", "B2/S/Cx", "B9/S/C3", "B2S/C"] {
+            assert!(GenerationsRule::from_str(s).is_err(), "{s:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn dying_cells_count_up_to_dead() {
+        let params = GenerationsParams {
+            alive_ratio: 0.0,
+            rule: rule("B2/S/C4"),
+        };
+
+        let offsets = Neighborhood::Shape(Shape::Moore, 1).offsets();
+        let alive = Generations { state: 1 };
+        let mut neighbors = vec![None; offsets.len()];
+        neighbors[0] = Some(&alive);
+        neighbors[1] = Some(&alive);
+
+        let mut rng = Rng::new(0);
+        let next = |cell: Generations, rng: &mut Rng| {
+            cell.next(&params, Neighbors::new(&offsets, &neighbors), rng).state
+        };
+
+        // born with two live neighbors, then decaying through states 2 and 3
+        assert_eq!(next(Generations { state: 0 }, &mut rng), 1);
+        assert_eq!(next(Generations { state: 1 }, &mut rng), 2);
+        assert_eq!(next(Generations { state: 2 }, &mut rng), 3);
+        assert_eq!(next(Generations { state: 3 }, &mut rng), 0);
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        let automata = Automata::<Generations>::new(params(30, 20), GenerationsParams {
+            alive_ratio: 0.3,
+            rule: rule("345/2/4"),
+        });
+
+        check_resume(automata, 10);
+
+        let mut writer = Writer::new();
+        writer.f32(0.5);
+        rule("B2/S/C3").life.write(&mut writer);
+        writer.u8(1);
+        assert!(Generations::read_params(&mut Reader::new(&writer.into_bytes())).is_none());
+    }
+}
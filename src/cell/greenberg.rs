@@ -0,0 +1,145 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::Color;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A cell in a Greenberg-Hastings excitable medium: 0 is resting, 1 is
+/// excited, and the states after that are refractory.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct GreenbergHastings {
+    state: u8,
+}
+
+pub struct GreenbergHastingsParams {
+    /// The number of excited neighbors that excite a resting cell
+    pub threshold: u8,
+    /// The color of each state, so there is one color per state
+    pub palette: Vec<Color>,
+}
+
+impl GreenbergHastingsParams {
+    fn states(&self) -> u8 {
+        self.palette.len() as u8
+    }
+}
+
+impl GreenbergHastings {
+    fn next<'a>(&'a self, params: &GreenbergHastingsParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        let state = match self.state {
+            0 => {
+                let count = neighbors.into_iter()
+                    .filter(|neighbor| neighbor.state == 1)
+                    .count();
+
+                (count >= params.threshold as usize) as u8
+            },
+            state => (state + 1) % params.states(),
+        };
+
+        GreenbergHastings { state }
+    }
+}
+
+impl Cell for GreenbergHastings {
+    type Params = GreenbergHastingsParams;
+    const NAME: &'static str = "greenberg-hastings";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        GreenbergHastings {
+            state: rng.gen_below(params.states() as usize) as u8,
+        }
+    }
+
+    fn color(&self, params: &Self::Params) -> Color {
+        params.palette[self.state as usize]
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(GreenbergHastings::next)
+    }
+
+    fn paint_states(params: &Self::Params) -> Vec<Self> {
+        (1..params.states())
+            .map(|state| GreenbergHastings { state })
+            .collect()
+    }
+
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> {
+        (state < params.states()).then_some(GreenbergHastings { state })
+    }
+
+    fn state(&self) -> u8 {
+        self.state
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.u8(params.threshold);
+        writer.u16(params.palette.len() as u16);
+        for color in params.palette.iter() {
+            for channel in [color.r, color.g, color.b, color.a] {
+                writer.f32(channel);
+            }
+        }
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        let threshold = reader.u8()?;
+        let len = reader.u16()? as usize;
+        let palette = (0..len)
+            .map(|_| Some(Color::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?)))
+            .collect::<Option<Vec<Color>>>()
+            .filter(|palette| (3..=255).contains(&palette.len()))?;
+
+        Some(GreenbergHastingsParams { threshold, palette })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::BLACK;
+    use crate::neighborhood::{Neighborhood, Shape};
+
+    /// Five states: resting, excited and three refractory states.
+    fn params(threshold: u8) -> GreenbergHastingsParams {
+        GreenbergHastingsParams {
+            threshold,
+            palette: vec![BLACK; 5],
+        }
+    }
+
+    /// The next state of a cell in `state` with neighbors in `states`.
+    fn next(params: &GreenbergHastingsParams, state: u8, states: &[u8]) -> u8 {
+        let offsets = Neighborhood::Shape(Shape::Moore, 1).offsets();
+        let cells: Vec<GreenbergHastings> = states.iter()
+            .map(|&state| GreenbergHastings { state })
+            .collect();
+
+        let mut neighbors: Vec<Option<&GreenbergHastings>> = vec![None; offsets.len()];
+        for (neighbor, cell) in neighbors.iter_mut().zip(cells.iter()) {
+            *neighbor = Some(cell);
+        }
+
+        let cell = GreenbergHastings { state };
+        cell.next(params, Neighbors::new(&offsets, &neighbors), &mut Rng::new(0)).state
+    }
+
+    #[test]
+    fn excited_cells_recover_before_resting() {
+        // excited neighbors do not matter outside of the resting state
+        let params = params(1);
+        assert_eq!(next(&params, 1, &[1, 1]), 2);
+        assert_eq!(next(&params, 2, &[1]), 3);
+        assert_eq!(next(&params, 3, &[1]), 4);
+        assert_eq!(next(&params, 4, &[1]), 0);
+    }
+
+    #[test]
+    fn resting_cells_need_enough_excited_neighbors() {
+        let params = params(2);
+        assert_eq!(next(&params, 0, &[]), 0);
+        assert_eq!(next(&params, 0, &[1, 2, 3, 4]), 0);
+        assert_eq!(next(&params, 0, &[1, 1]), 1);
+        assert_eq!(next(&params, 0, &[1, 1, 1, 0]), 1);
+    }
+}
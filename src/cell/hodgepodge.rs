@@ -0,0 +1,177 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::Color;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A cell in Gerhardt and Schuster's hodgepodge machine: 0 is healthy, the
+/// last state is ill, and every state in between is infected.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Hodgepodge {
+    state: u8,
+}
+
+pub struct HodgepodgeParams {
+    /// How many infected neighbors it takes to infect a healthy cell further
+    pub k1: u8,
+    /// How many ill neighbors it takes to infect a healthy cell further
+    pub k2: u8,
+    /// How quickly infected cells get worse
+    pub g: u8,
+    /// The color of each state, so there is one color per state
+    pub palette: Vec<Color>,
+}
+
+impl HodgepodgeParams {
+    fn ill(&self) -> u8 {
+        (self.palette.len() - 1) as u8
+    }
+}
+
+impl Hodgepodge {
+    fn next<'a>(&'a self, params: &HodgepodgeParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        let ill = params.ill();
+        let (mut infected, mut ills, mut sum) = (0, 0, self.state as u32);
+        for neighbor in neighbors {
+            match neighbor.state {
+                0 => {},
+                state if state == ill => ills += 1,
+                _ => infected += 1,
+            }
+
+            sum += neighbor.state as u32;
+        }
+
+        let state = match self.state {
+            0 => infected / params.k1 as u32 + ills / params.k2 as u32,
+            state if state == ill => 0,
+            _ => sum / (infected + ills + 1) + params.g as u32,
+        };
+
+        Hodgepodge {
+            state: state.min(ill as u32) as u8,
+        }
+    }
+}
+
+impl Cell for Hodgepodge {
+    type Params = HodgepodgeParams;
+    const NAME: &'static str = "hodgepodge";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        Hodgepodge {
+            state: rng.gen_below(params.palette.len()) as u8,
+        }
+    }
+
+    fn color(&self, params: &Self::Params) -> Color {
+        params.palette[self.state as usize]
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(Hodgepodge::next)
+    }
+
+    fn paint_states(params: &Self::Params) -> Vec<Self> {
+        vec![Hodgepodge { state: params.ill() }]
+    }
+
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> {
+        (state <= params.ill()).then_some(Hodgepodge { state })
+    }
+
+    fn state(&self) -> u8 {
+        self.state
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.u8(params.k1);
+        writer.u8(params.k2);
+        writer.u8(params.g);
+        writer.u16(params.palette.len() as u16);
+        for color in params.palette.iter() {
+            for channel in [color.r, color.g, color.b, color.a] {
+                writer.f32(channel);
+            }
+        }
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        let (k1, k2, g) = (reader.u8()?, reader.u8()?, reader.u8()?);
+        let len = reader.u16()? as usize;
+        let palette = (0..len)
+            .map(|_| Some(Color::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?)))
+            .collect::<Option<Vec<Color>>>()
+            .filter(|palette| (3..=256).contains(&palette.len()))?;
+
+        (k1 > 0 && k2 > 0).then_some(HodgepodgeParams { k1, k2, g, palette })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::BLACK;
+    use crate::neighborhood::{Neighborhood, Shape};
+
+    /// States 0 through `ill`, with the given k1, k2 and g.
+    fn params(ill: u8, k1: u8, k2: u8, g: u8) -> HodgepodgeParams {
+        HodgepodgeParams {
+            k1,
+            k2,
+            g,
+            palette: vec![BLACK; ill as usize + 1],
+        }
+    }
+
+    /// The next state of a cell in `state` with neighbors in `states`.
+    fn next(params: &HodgepodgeParams, state: u8, states: &[u8]) -> u8 {
+        let offsets = Neighborhood::Shape(Shape::Moore, 1).offsets();
+        let cells: Vec<Hodgepodge> = states.iter()
+            .map(|&state| Hodgepodge { state })
+            .collect();
+
+        let mut neighbors: Vec<Option<&Hodgepodge>> = vec![None; offsets.len()];
+        for (neighbor, cell) in neighbors.iter_mut().zip(cells.iter()) {
+            *neighbor = Some(cell);
+        }
+
+        let cell = Hodgepodge { state };
+        cell.next(params, Neighbors::new(&offsets, &neighbors), &mut Rng::new(0)).state
+    }
+
+    #[test]
+    fn healthy_cells_catch_it_from_infected_and_ill_neighbors() {
+        let params = params(100, 2, 3, 34);
+        assert_eq!(next(&params, 0, &[0, 0, 0]), 0);
+
+        // infected / k1 + ill / k2, rounded down
+        assert_eq!(next(&params, 0, &[10, 20, 30]), 1);
+        assert_eq!(next(&params, 0, &[10, 20, 30, 100, 100]), 1);
+        assert_eq!(next(&params, 0, &[10, 20, 30, 100, 100, 100]), 2);
+    }
+
+    #[test]
+    fn infected_cells_average_their_neighbors_and_get_worse() {
+        // (10 + 20 + 0 + 100) / (1 infected + 1 ill + 1) + g, where healthy
+        // neighbors are left out of the count
+        let params = params(100, 2, 3, 34);
+        assert_eq!(next(&params, 10, &[20, 0, 100]), 77);
+        assert_eq!(next(&params, 10, &[]), 44);
+    }
+
+    #[test]
+    fn ill_cells_recover() {
+        let params = params(100, 2, 3, 34);
+        assert_eq!(next(&params, 100, &[100, 100, 50]), 0);
+    }
+
+    #[test]
+    fn cells_never_get_worse_than_ill() {
+        let many_states = params(100, 2, 3, 34);
+        assert_eq!(next(&many_states, 90, &[100, 100]), 100);
+
+        // healthy cells with more sick neighbors than states
+        let few_states = params(2, 1, 1, 1);
+        assert_eq!(next(&few_states, 0, &[1, 1, 1, 2, 2, 2, 2, 2]), 2);
+    }
+}
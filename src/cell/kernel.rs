@@ -0,0 +1,79 @@
+use crate::cell::Neighbors;
+use crate::neighborhood::{Mask, Neighborhood};
+
+/// Weights over a round neighborhood, for cells with continuous values that
+/// take weighted averages of their neighbors.
+///
+/// The neighborhood only includes offsets with a weight, so cells using a
+/// kernel should return `Kernel::neighborhood` from `Cell::neighborhood` to
+/// line their neighbors up with the weights.
+#[derive(Clone, Debug)]
+pub struct Kernel {
+    offsets: Box<[(isize, isize)]>,
+    /// One list of weights per weight function, lined up with the offsets
+    weights: Vec<Box<[f32]>>,
+    /// The weight of the cell itself for each weight function
+    center: Vec<f32>,
+}
+
+impl Kernel {
+    /// Weighs every offset within `radius` with each of the weight functions,
+    /// which are given the distance from the center in cells. Each list of
+    /// weights is normalized to add up to 1, including `center`, the weight
+    /// of the cell itself. A weight function with no weight anywhere is left
+    /// at 0, see `has_weight`.
+    pub fn new(radius: usize, center: &[f32], weight_fns: &[&dyn Fn(f32) -> f32]) -> Kernel {
+        let radius = radius as isize;
+        let (offsets, raw): (Vec<(isize, isize)>, Vec<Vec<f32>>) = (-radius..=radius)
+            .flat_map(|drow| (-radius..=radius).map(move |dcol| (dcol, drow)))
+            .filter(|&offset| offset != (0, 0))
+            .map(|(dcol, drow)| {
+                let distance = ((dcol * dcol + drow * drow) as f32).sqrt();
+                ((dcol, drow), weight_fns.iter().map(|weight_fn| weight_fn(distance)).collect::<Vec<f32>>())
+            })
+            .filter(|(_, weights)| weights.iter().any(|&weight| weight > 0.0))
+            .unzip();
+
+        let totals: Vec<f32> = (0..weight_fns.len())
+            .map(|n| raw.iter().map(|weights| weights[n]).sum::<f32>() + center[n])
+            .collect();
+
+        let normalize = |weight: f32, total: f32| match total > 0.0 {
+            true => weight / total,
+            false => 0.0,
+        };
+
+        let weights = totals.iter()
+            .enumerate()
+            .map(|(n, &total)| raw.iter().map(|weights| normalize(weights[n], total)).collect())
+            .collect();
+
+        Kernel {
+            offsets: offsets.into_boxed_slice(),
+            weights,
+            center: center.iter().zip(totals.iter()).map(|(&center, &total)| normalize(center, total)).collect(),
+        }
+    }
+
+    /// Whether the weight function at `index` gives any weight to the cell or
+    /// its neighbors. Without any weight, its sums are always 0.
+    pub fn has_weight(&self, index: usize) -> bool {
+        self.center[index] > 0.0 || self.weights[index].iter().any(|&weight| weight > 0.0)
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        Neighborhood::Custom(Mask::from_offsets(self.offsets.clone()))
+    }
+
+    /// The weighted sum of the cell's and its neighbors' values with the
+    /// weights from the weight function at `index`. Neighbors outside of the
+    /// grid count as 0.
+    pub fn sum<C>(&self, index: usize, cell: &C, neighbors: Neighbors<C>, value: impl Fn(&C) -> f32) -> f32 {
+        let neighbors_sum: f32 = self.weights[index].iter()
+            .zip(neighbors.as_slice())
+            .filter_map(|(weight, neighbor)| Some(weight * value((*neighbor)?)))
+            .sum();
+
+        neighbors_sum + self.center[index] * value(cell)
+    }
+}
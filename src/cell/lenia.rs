@@ -0,0 +1,218 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::cell::kernel::Kernel;
+use crate::color::*;
+use crate::neighborhood::Neighborhood;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// The colors of Lenia cells from 0 to 1.
+pub static LENIA_COLORS: &[Color] = &[BLACK, DARKPURPLE, BLUE, SKYBLUE, YELLOW, WHITE];
+
+/// A cell in a Lenia automata, with a value from 0 to 1.
+#[derive(Clone, Default, PartialEq)]
+pub struct Lenia {
+    value: f32,
+}
+
+pub struct LeniaParams {
+    pub alive_ratio: f32,
+    /// The radius of the kernel in cells
+    pub range: usize,
+    /// The heights of the kernel's rings, from the center out
+    pub peaks: Vec<f32>,
+    /// The center and width of the growth function
+    pub mu: f32,
+    pub sigma: f32,
+    /// The time step, where smaller steps are smoother
+    pub dt: f32,
+    kernel: Kernel,
+}
+
+impl LeniaParams {
+    pub fn new(alive_ratio: f32, range: usize, peaks: Vec<f32>, mu: f32, sigma: f32, dt: f32) -> LeniaParams {
+        // each ring is a smooth bump, scaled by its peak
+        let shell = |distance: f32| {
+            let r = distance / range as f32;
+            if r >= 1.0 {
+                return 0.0;
+            }
+
+            let rings = peaks.len() as f32;
+            let peak = peaks[(r * rings) as usize];
+            let r = (r * rings).fract();
+            match r > 0.0 {
+                true => peak * (4.0 - 1.0 / (r * (1.0 - r))).exp(),
+                false => 0.0,
+            }
+        };
+
+        let kernel = Kernel::new(range, &[0.0], &[&shell]);
+        LeniaParams { alive_ratio, range, peaks, mu, sigma, dt, kernel }
+    }
+
+    /// Whether the kernel has any weight. Small ranges can fall between the
+    /// rings, and all-zero peaks flatten them, leaving cells with nothing to
+    /// grow from.
+    pub fn has_weight(&self) -> bool {
+        self.kernel.has_weight(0)
+    }
+
+    fn growth(&self, potential: f32) -> f32 {
+        let x = (potential - self.mu) / self.sigma;
+        2.0 * (-x * x / 2.0).exp() - 1.0
+    }
+}
+
+impl Lenia {
+    fn next<'a>(&'a self, params: &LeniaParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        let potential = params.kernel.sum(0, self, neighbors, |cell| cell.value);
+        let value = self.value + params.dt * params.growth(potential);
+        Lenia {
+            value: value.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Cell for Lenia {
+    type Params = LeniaParams;
+    const NAME: &'static str = "lenia";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        let value = match rng.gen_bool(params.alive_ratio) {
+            true => rng.gen_f32(),
+            false => 0.0,
+        };
+
+        Lenia { value }
+    }
+
+    fn color(&self, _params: &Self::Params) -> Color {
+        Color::gradient(LENIA_COLORS, self.value)
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(Lenia::next)
+    }
+
+    fn neighborhood(params: &Self::Params) -> Option<Neighborhood> {
+        Some(params.kernel.neighborhood())
+    }
+
+    fn differs(&self, other: &Self) -> bool {
+        self.state() != other.state()
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        [1.0, 0.75, 0.5, 0.25].into_iter()
+            .map(|value| Lenia { value })
+            .collect()
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        Some(Lenia {
+            value: state as f32 / 255.0,
+        })
+    }
+
+    /// The value rounded to one of 256 levels.
+    fn state(&self) -> u8 {
+        (self.value * 255.0).round() as u8
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.f32(self.value);
+    }
+
+    fn read(reader: &mut Reader, _params: &Self::Params) -> Option<Self> {
+        let value = reader.f32()?;
+        (0.0..=1.0).contains(&value).then_some(Lenia { value })
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.alive_ratio);
+        writer.u64(params.range as u64);
+        writer.u16(params.peaks.len() as u16);
+        for &peak in params.peaks.iter() {
+            writer.f32(peak);
+        }
+
+        writer.f32(params.mu);
+        writer.f32(params.sigma);
+        writer.f32(params.dt);
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        let alive_ratio = reader.f32()?;
+        let range = reader.u64()? as usize;
+        if !(2..=32).contains(&range) {
+            return None;
+        }
+
+        let peaks = (0..reader.u16()?)
+            .map(|_| reader.f32())
+            .collect::<Option<Vec<f32>>>()?;
+
+        if peaks.is_empty() || !peaks.iter().all(|peak| (0.0..=1.0).contains(peak)) || peaks.iter().all(|&peak| peak == 0.0) {
+            return None;
+        }
+
+        let (mu, sigma, dt) = (reader.f32()?, reader.f32()?, reader.f32()?);
+        if !(sigma.is_finite() && sigma > 0.0 && dt > 0.0 && dt <= 1.0) {
+            return None;
+        }
+
+        Some(LeniaParams::new(alive_ratio, range, peaks, mu, sigma, dt))
+            .filter(LeniaParams::has_weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::Automata;
+    use crate::automata::tests::{check_resume, params};
+
+    fn params_with(sigma: f32, dt: f32) -> LeniaParams {
+        LeniaParams::new(0.5, 5, vec![1.0, 0.5], 0.15, sigma, dt)
+    }
+
+    fn read(params: LeniaParams) -> bool {
+        let mut writer = Writer::new();
+        Lenia::write_params(&params, &mut writer);
+        Lenia::read_params(&mut Reader::new(&writer.into_bytes())).is_some()
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        check_resume(Automata::<Lenia>::new(params(30, 20), params_with(0.015, 0.1)), 10);
+    }
+
+    #[test]
+    fn rejects_snapshots_with_bad_growth_or_steps() {
+        assert!(read(params_with(0.015, 1.0)));
+        for (sigma, dt) in [(0.0, 0.1), (-0.1, 0.1), (f32::NAN, 0.1), (0.015, 0.0), (0.015, 1.5), (0.015, f32::NAN)] {
+            assert!(!read(params_with(sigma, dt)), "sigma {sigma}, dt {dt} should not load");
+        }
+    }
+
+    #[test]
+    fn kernels_without_weight_are_rejected() {
+        let weighted = LeniaParams::new(0.5, 2, vec![1.0], 0.15, 0.015, 0.1);
+        assert!(weighted.has_weight());
+        assert!(read(weighted));
+
+        // a range of 1 only reaches the edge of the ring, where it has no weight
+        for (range, peaks) in [(1, vec![1.0]), (5, vec![0.0]), (5, vec![0.0, 0.0])] {
+            let params = LeniaParams::new(0.5, range, peaks.clone(), 0.15, 0.015, 0.1);
+            assert!(!params.has_weight(), "range {range}, peaks {peaks:?} should have no weight");
+            assert!(!read(params), "range {range}, peaks {peaks:?} should not load");
+        }
+    }
+
+    #[test]
+    fn kernels_without_weight_leave_cells_finite() {
+        let params = LeniaParams::new(0.5, 1, vec![1.0], 0.15, 0.015, 0.1);
+        let next = Lenia { value: 0.5 }.next(&params, Neighbors::new(&[], &[]), &mut Rng::new(1));
+        assert!(next.value.is_finite());
+    }
+}
@@ -1,18 +1,30 @@
+use std::fmt;
 use std::str::FromStr;
-use macroquad::prelude::*;
-use macroquad::rand::gen_range;
-use crate::cell::Cell;
+use std::sync::LazyLock;
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub enum Life {
+    #[default]
     Dead,
     Alive,
 }
 
+/// The birth and survival conditions of a Life-like rule.
+///
+/// Rules in Hensel notation (e.g. "B2n3/S23-q") also depend on where the
+/// live neighbors are, which only makes sense in the Moore neighborhood with
+/// radius 1. Those rules are not totalistic, see `is_totalistic`.
 #[derive(Clone, Debug)]
 pub struct LifeRule {
-    birth: u16,
-    survive: u16,
+    /// For each neighbor count, the set of Hensel letters (as bits in the
+    /// order of `hensel_letters`) that cause a birth or survival
+    birth: [u16; 9],
+    survive: [u16; 9],
+    totalistic: bool,
 }
 
 #[allow(dead_code)] // ignore warning for 'reason' never read
@@ -28,27 +40,183 @@ pub enum RuleParseReason {
     DigitParse,
 }
 
+/// Representatives of each Hensel letter for 1 through 4 live neighbors, as
+/// masks of the 3x3 neighborhood with NW = 1, N = 2, NE = 4, W = 8, E = 32,
+/// SW = 64, S = 128 and SE = 256. Counts of 5 through 7 use the complement of
+/// the same letter for 8 minus the count.
+const HENSEL_NEIGHBORHOODS: [&[u16]; 4] = [
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// The (count, letter) of every arrangement of live Moore neighbors, indexed
+/// by the mask built in `arrangement`.
+static HENSEL_TABLE: LazyLock<[(u8, u8); 256]> = LazyLock::new(|| {
+    // the 8 rotations and reflections of the 3x3 neighborhood
+    let symmetries = |dcol: isize, drow: isize| [
+        (dcol, drow), (-dcol, drow), (dcol, -drow), (-dcol, -drow),
+        (drow, dcol), (-drow, dcol), (drow, -dcol), (-drow, -dcol),
+    ];
+
+    let mut table = [(0, 0); 256];
+    for (count, letters) in (1..).zip(HENSEL_NEIGHBORHOODS) {
+        for (letter, &neighborhood) in (0..).zip(letters) {
+            // convert the representative to the offset order of the neighborhood
+            let offsets: Vec<(isize, isize)> = (0..9)
+                .filter(|bit| bit != &4 && neighborhood & (1 << bit) > 0)
+                .map(|bit| (bit % 3 - 1, bit / 3 - 1))
+                .collect();
+
+            for symmetry in 0..8 {
+                let mask = offsets.iter()
+                    .map(|&(dcol, drow)| {
+                        let (dcol, drow) = symmetries(dcol, drow)[symmetry];
+                        moore_bit(dcol, drow)
+                    })
+                    .fold(0, |mask, bit| mask | bit);
+
+                table[mask as usize] = (count, letter);
+                if count < 4 {
+                    table[!mask as usize] = (8 - count, letter);
+                }
+            }
+        }
+    }
+
+    table[255] = (8, 0);
+    table
+});
+
+/// The Hensel letters for each number of live neighbors.
+fn hensel_letters(count: usize) -> &'static str {
+    match count {
+        1 | 7 => "ce",
+        2 | 6 => "ceaikn",
+        3 | 5 => "ceaiknjqry",
+        4 => "ceaiknjqrytwz",
+        _ => "",
+    }
+}
+
+/// Every letter for a count, which is a single bit for counts without letters.
+fn all_letters(count: usize) -> u16 {
+    (1 << hensel_letters(count).len().max(1)) - 1
+}
+
+/// The bit of a neighbor in a Moore neighborhood arrangement, in the order of
+/// the neighborhood offsets.
+fn moore_bit(dcol: isize, drow: isize) -> u8 {
+    let position = (drow + 1) * 3 + (dcol + 1);
+    let position = if position > 4 { position - 1 } else { position };
+    1 << position
+}
+
+/// Builds a mask of the live neighbors in the Moore neighborhood with radius
+/// 1, for use with `LifeRule::is_birth_arrangement` and
+/// `LifeRule::is_survival_arrangement`.
+pub fn arrangement<C>(neighbors: Neighbors<C>, is_alive: impl Fn(&C) -> bool) -> u8 {
+    neighbors.with_offsets()
+        .filter(|&((dcol, drow), cell)| dcol.abs() <= 1 && drow.abs() <= 1 && is_alive(cell))
+        .fold(0, |mask, ((dcol, drow), _)| mask | moore_bit(dcol, drow))
+}
+
+/// Counts the live neighbors of each color, for Life variants where live
+/// cells have a color. `color` gives the color of a live neighbor, from 0
+/// through 3, or `None` for dead neighbors.
+pub fn tally<C>(neighbors: Neighbors<C>, color: impl Fn(&C) -> Option<usize>) -> [usize; 4] {
+    neighbors.into_iter()
+        .filter_map(color)
+        .fold([0; 4], |mut counts, color| {
+            counts[color] += 1;
+            counts
+        })
+}
+
 impl LifeRule {
-    fn from_digits(mut chars: impl Iterator<Item = char>) -> Result<u16, RuleParseError> {
-        let mut rule: u16 = 0;
+    fn new(birth: [u16; 9], survive: [u16; 9]) -> LifeRule {
+        let totalistic = (0..=8).all(|count| {
+            [birth[count], survive[count]].iter()
+                .all(|&letters| letters == 0 || letters == all_letters(count))
+        });
+
+        LifeRule { birth, survive, totalistic }
+    }
+
+    pub fn is_birth(&self, count: usize) -> bool {
+        count <= 8 && self.birth[count] == all_letters(count)
+    }
+
+    pub fn is_survival(&self, count: usize) -> bool {
+        count <= 8 && self.survive[count] == all_letters(count)
+    }
+
+    pub fn is_birth_arrangement(&self, mask: u8) -> bool {
+        let (count, letter) = HENSEL_TABLE[mask as usize];
+        self.birth[count as usize] & (1 << letter) > 0
+    }
+
+    pub fn is_survival_arrangement(&self, mask: u8) -> bool {
+        let (count, letter) = HENSEL_TABLE[mask as usize];
+        self.survive[count as usize] & (1 << letter) > 0
+    }
+
+    /// Whether the rule only depends on the number of live neighbors, rather
+    /// than where they are.
+    pub fn is_totalistic(&self) -> bool {
+        self.totalistic
+    }
+
+    pub fn write(&self, writer: &mut Writer) {
+        writer.str(&self.to_string());
+    }
+
+    pub fn read(reader: &mut Reader) -> Option<LifeRule> {
+        LifeRule::from_str(reader.str()?).ok()
+    }
+
+    fn from_digits(chars: impl Iterator<Item = char>) -> Result<[u16; 9], RuleParseError> {
+        let mut rule = [0; 9];
+        let mut chars = chars.peekable();
         while let Some(char) = chars.next() {
-            match char.to_digit(10) {
-                Some(digit) => {
-                    if digit > 8 {
-                        return Err(RuleParseError {
-                            message: "digit can only be 0 through 8",
-                            reason: RuleParseReason::DigitParse
-                        });
-                    }
-
-                    // otherwise, update the rule
-                    rule |= 1 << digit;
-                },
+            let count = match char.to_digit(10) {
+                Some(digit) if digit > 8 => return Err(RuleParseError {
+                    message: "digit can only be 0 through 8",
+                    reason: RuleParseReason::DigitParse
+                }),
+                Some(digit) => digit as usize,
                 None => return Err(RuleParseError {
-                    message: "rule can only include digits",
+                    message: "rule can only include digits and Hensel letters",
+                    reason: RuleParseReason::DigitParse
+                }),
+            };
+
+            // a digit may be followed by the letters to include, or by '-'
+            // and the letters to exclude
+            let exclude = chars.next_if_eq(&'-').is_some();
+            let mut letters: u16 = 0;
+            while let Some(letter) = chars.next_if(char::is_ascii_lowercase) {
+                let index = hensel_letters(count).find(letter).ok_or(RuleParseError {
+                    message: "Hensel letter is not valid for its number of neighbors",
                     reason: RuleParseReason::DigitParse
-                })
+                })?;
+
+                letters |= 1 << index;
+            }
+
+            if exclude && letters == 0 {
+                return Err(RuleParseError {
+                    message: "'-' must be followed by Hensel letters",
+                    reason: RuleParseReason::Format
+                });
             }
+
+            rule[count] |= match (exclude, letters) {
+                (false, 0) => all_letters(count),
+                (false, letters) => letters,
+                (true, letters) => all_letters(count) & !letters,
+            };
         }
 
         Ok(rule)
@@ -58,28 +226,77 @@ impl LifeRule {
 impl FromStr for LifeRule {
     type Err = RuleParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Hensel letters are lowercase, so the rest of the rule is too
+        let s = s.to_ascii_lowercase();
+
+        // rules without 'b' or 's' are in S/B notation, e.g. "23/3"
+        if !s.contains(['b', 's']) {
+            let (survive, birth) = s.split_once('/').ok_or(RuleParseError {
+                message: "life rule must include a birth and survival rule",
+                reason: RuleParseReason::Format
+            })?;
+
+            return Ok(LifeRule::new(
+                LifeRule::from_digits(birth.chars())?,
+                LifeRule::from_digits(survive.chars())?
+            ));
+        }
+
+        // otherwise, the rule is in B/S notation with an optional slash
+        let s = s.replace('/', "");
         let mut chars = s.chars().peekable();
 
-        // check that the string starts with a 'B'
-        if chars.next() != Some('B') {
+        // check that the string starts with a 'b'
+        if chars.next() != Some('b') {
             return Err(RuleParseError {
                 message: "life rule must start with a birth rule",
                 reason: RuleParseReason::Format
             });
         }
 
-        // find the index of the 'S'
-        let survive_start = s.find('S').ok_or_else(|| RuleParseError {
+        // find the index of the 's'
+        let survive_start = s.find('s').ok_or(RuleParseError {
             message: "life rule must include a survival rule",
             reason: RuleParseReason::Format
         })?;
 
         let birth_chars = s.chars().take(survive_start).skip(1);
         let survive_chars = s.chars().skip(survive_start + 1);
-        Ok(LifeRule {
-            birth: LifeRule::from_digits(birth_chars)?,
-            survive: LifeRule::from_digits(survive_chars)?
-        })
+        Ok(LifeRule::new(
+            LifeRule::from_digits(birth_chars)?,
+            LifeRule::from_digits(survive_chars)?
+        ))
+    }
+}
+
+impl fmt::Display for LifeRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |rule: &[u16; 9]| -> String {
+            let mut digits = String::new();
+            for (count, &letters) in rule.iter().enumerate().filter(|(_, letters)| **letters > 0) {
+                digits.push(char::from(b'0' + count as u8));
+                if letters == all_letters(count) {
+                    continue;
+                }
+
+                // write whichever of the included or excluded letters is shorter
+                let hensel = hensel_letters(count);
+                let included = letters.count_ones() as usize;
+                let exclude = included * 2 > hensel.len();
+                if exclude {
+                    digits.push('-');
+                }
+
+                digits.extend(hensel.chars()
+                    .enumerate()
+                    .filter(|(index, _)| (letters & (1 << index) > 0) != exclude)
+                    .map(|(_, letter)| letter));
+            }
+
+            digits
+        };
+
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survive))
     }
 }
 
@@ -88,11 +305,42 @@ pub struct LifeParams {
     pub rule: LifeRule,
 }
 
+impl Life {
+    fn next<'a>(&'a self, params: &LifeParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self
+    {
+        let rule = &params.rule;
+        let alive = if rule.is_totalistic() {
+            let count = neighbors.into_iter()
+                .filter(|neighbor| **neighbor == Life::Alive)
+                .count();
+
+            match *self {
+                Life::Dead => rule.is_birth(count),
+                Life::Alive => rule.is_survival(count),
+            }
+        }
+        else {
+            // non-totalistic rules depend on where the live neighbors are
+            let mask = arrangement(neighbors, |neighbor| *neighbor == Life::Alive);
+            match *self {
+                Life::Dead => rule.is_birth_arrangement(mask),
+                Life::Alive => rule.is_survival_arrangement(mask),
+            }
+        };
+
+        match alive {
+            true => Life::Alive,
+            false => Life::Dead,
+        }
+    }
+}
+
 impl Cell for Life {
     type Params = LifeParams;
+    const NAME: &'static str = "life";
 
-    fn new(params: &Self::Params) -> Self {
-        if gen_range(0.0, 1.0) < params.alive_ratio {
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        if rng.gen_bool(params.alive_ratio) {
             Life::Alive
         }
         else {
@@ -100,34 +348,198 @@ impl Cell for Life {
         }
     }
 
-    fn next<'a>(&'a self, params: &Self::Params, neighbors: impl IntoIterator<Item = &'a Self>) -> Self
-    {
-        let count = neighbors.into_iter()
-            .filter(|neighbor| **neighbor == Life::Alive)
-            .count();
-
+    fn color(&self, _params: &Self::Params) -> Color {
         match *self {
-            Life::Dead => match params.rule.birth & (1 << count) > 0 {
-                true => Life::Alive,
-                false => Life::Dead
-            },
-            Life::Alive => match params.rule.survive & (1 << count) > 0 {
-                true => Life::Alive,
-                false => Life::Dead,
-            },
+            Life::Dead => WHITE,
+            Life::Alive => BLACK,
         }
     }
 
-    fn color(&self, _params: &Self::Params) -> Color {
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(Life::next)
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        vec![Life::Alive]
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        match state {
+            0 => Some(Life::Dead),
+            1 => Some(Life::Alive),
+            _ => None,
+        }
+    }
+
+    fn state(&self) -> u8 {
         match *self {
-            Life::Dead => WHITE,
-            Life::Alive => BLACK,
+            Life::Dead => 0,
+            Life::Alive => 1,
         }
     }
+
+    fn rule(params: &Self::Params) -> Option<String> {
+        Some(params.rule.to_string())
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.alive_ratio);
+        params.rule.write(writer);
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        Some(LifeParams {
+            alive_ratio: reader.f32()?,
+            rule: LifeRule::read(reader)?,
+        })
+    }
 }
 
-impl Default for Life {
-    fn default() -> Self {
-        Life::Dead
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::Automata;
+    use crate::automata::tests::{check_resume, params};
+
+    fn rule(s: &str) -> LifeRule {
+        LifeRule::from_str(s).unwrap()
+    }
+
+    /// The arrangement of live neighbors at the given offsets.
+    fn mask(offsets: &[(isize, isize)]) -> u8 {
+        offsets.iter().fold(0, |mask, &(dcol, drow)| mask | moore_bit(dcol, drow))
+    }
+
+    /// Every arrangement the same as `mask` after rotating or reflecting it.
+    fn symmetries(arrangement: u8) -> Vec<u8> {
+        let offsets: Vec<(isize, isize)> = (-1..=1)
+            .flat_map(|drow| (-1..=1).map(move |dcol| (dcol, drow)))
+            .filter(|&offset| offset != (0, 0) && arrangement & moore_bit(offset.0, offset.1) > 0)
+            .collect();
+
+        let transform = |dcol: isize, drow: isize| [
+            (dcol, drow), (-dcol, drow), (dcol, -drow), (-dcol, -drow),
+            (drow, dcol), (-drow, dcol), (drow, -dcol), (-drow, -dcol),
+        ];
+
+        (0..8)
+            .map(|symmetry| {
+                let moved: Vec<_> = offsets.iter().map(|&(dcol, drow)| transform(dcol, drow)[symmetry]).collect();
+                mask(&moved)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_arrangement_has_exactly_one_class() {
+        for count in 0..=8 {
+            let letters = hensel_letters(count).len().max(1);
+            let masks: Vec<u8> = (0..=255u8).filter(|mask| mask.count_ones() as usize == count).collect();
+
+            for letter in 0..letters {
+                let class: Vec<u8> = masks.iter()
+                    .copied()
+                    .filter(|&mask| HENSEL_TABLE[mask as usize] == (count as u8, letter as u8))
+                    .collect();
+
+                // each letter is one whole set of rotations and reflections
+                assert!(!class.is_empty(), "{count}{} has no arrangements", hensel_letters(count));
+                let mut orbit = symmetries(class[0]);
+                orbit.sort();
+                orbit.dedup();
+                assert_eq!(class, orbit, "count {count} letter {letter}");
+            }
+
+            // and every arrangement has the count of its live neighbors and
+            // one of the letters for that count
+            for &mask in masks.iter() {
+                let (mask_count, letter) = HENSEL_TABLE[mask as usize];
+                assert_eq!(mask_count as usize, count);
+                assert!((letter as usize) < letters);
+            }
+        }
+    }
+
+    #[test]
+    fn expanded_hensel_rules_match_totalistic_rules() {
+        let life = rule("B3/S23");
+        let expanded = rule("B3aceijknqry/S2aceikn3aceijknqry");
+        assert!(life.is_totalistic() && expanded.is_totalistic());
+        assert_eq!((life.birth, life.survive), (expanded.birth, expanded.survive));
+        assert_eq!(expanded.to_string(), "B3/S23");
+
+        for mask in 0..=255u8 {
+            assert_eq!(life.is_birth_arrangement(mask), expanded.is_birth_arrangement(mask));
+            assert_eq!(life.is_survival_arrangement(mask), expanded.is_survival_arrangement(mask));
+            assert_eq!(life.is_birth_arrangement(mask), mask.count_ones() == 3);
+        }
+    }
+
+    #[test]
+    fn isotropic_rules_match_reference_transitions() {
+        let rule = rule("B2-a/S12");
+        assert!(!rule.is_totalistic());
+
+        // births with 2 neighbors, except for an edge next to a corner (2a)
+        let births = [
+            (&[(0, -1), (1, -1)][..], false),
+            (&[(-1, 0), (-1, 1)], false),
+            (&[(-1, -1), (1, -1)], true),
+            (&[(0, -1), (-1, 0)], true),
+            (&[(-1, 0), (1, 0)], true),
+            (&[(-1, -1), (1, 0)], true),
+            (&[(1, -1), (-1, 1)], true),
+            (&[(0, -1)], false),
+            (&[(0, -1), (1, -1), (1, 0)], false),
+        ];
+
+        for (offsets, birth) in births {
+            assert_eq!(rule.is_birth_arrangement(mask(offsets)), birth, "birth with {offsets:?}");
+        }
+
+        // survival with 1 or 2 neighbors in any arrangement
+        for mask in 0..=255u8 {
+            let survival = matches!(mask.count_ones(), 1 | 2);
+            assert_eq!(rule.is_survival_arrangement(mask), survival, "survival with {mask:#010b}");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let rules = [
+            ("B3/S23", "B3/S23"),
+            ("23/3", "B3/S23"),
+            ("b36s23", "B36/S23"),
+            ("B2-a/S12", "B2-a/S12"),
+            ("B2n3/S23-q", "B2n3/S23-q"),
+            ("B2ce3-ae/S1e2a", "B2ce3-ea/S1e2a"),
+            ("B2ceaikn/S", "B2/S"),
+            ("B/S012345678", "B/S012345678"),
+        ];
+
+        for (s, expected) in rules {
+            let parsed = rule(s);
+            assert_eq!(parsed.to_string(), expected);
+
+            let again = rule(&parsed.to_string());
+            assert_eq!((again.birth, again.survive), (parsed.birth, parsed.survive));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        for s in ["", "B3", "S23", "23", "B9/S23", "B3/S2z", "B1a/S", "B2-/S", "X3/S23"] {
+            assert!(LifeRule::from_str(s).is_err(), "{s:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        let automata = Automata::<Life>::new(params(30, 20), LifeParams {
+            alive_ratio: 0.4,
+            rule: rule("B2n3/S23-q"),
+        });
+
+        check_resume(automata, 10);
     }
 }
@@ -0,0 +1,310 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use crate::cell::{Cell, UpdateMode};
+use crate::cell::life::{RuleParseError, RuleParseReason};
+use crate::color::*;
+use crate::neighborhood::{Neighborhood, Shape};
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A cell in a Larger than Life automata: 0 is dead, 1 is alive, and any
+/// state after that is dying, as in Generations.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Ltl {
+    state: u8,
+}
+
+/// A Larger than Life rule in Golly's notation, e.g. Bosco's rule
+/// "R5,C0,M1,S34..58,B34..45,NM".
+#[derive(Clone, Debug)]
+pub struct LtlRule {
+    pub radius: usize,
+    /// The number of states, where 0 and 1 both mean two states
+    pub states: u8,
+    /// Whether a live cell counts itself as a neighbor
+    pub middle: bool,
+    pub survive: RangeInclusive<u32>,
+    pub birth: RangeInclusive<u32>,
+    pub shape: Shape,
+}
+
+impl LtlRule {
+    fn parse_range(s: &str) -> Result<RangeInclusive<u32>, RuleParseError> {
+        let digit_err = |_| RuleParseError {
+            message: "ltl rule ranges must be a number or 'min..max'",
+            reason: RuleParseReason::DigitParse
+        };
+
+        let (min, max) = s.split_once("..").unwrap_or((s, s));
+        Ok(min.parse().map_err(digit_err)?..=max.parse().map_err(digit_err)?)
+    }
+
+    /// The number of states, counting dead and alive.
+    fn total_states(&self) -> u8 {
+        self.states.max(2)
+    }
+}
+
+impl FromStr for LtlRule {
+    type Err = RuleParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut radius, mut states, mut middle) = (None, 0, false);
+        let (mut survive, mut birth, mut shape) = (None, None, Shape::Moore);
+        for field in s.to_ascii_uppercase().split(',') {
+            let field = field.trim();
+            let (key, value) = field.split_at(field.chars().next().map_or(0, char::len_utf8));
+            match key {
+                "R" => radius = value.parse().ok(),
+                "C" => states = value.parse().map_err(|_| RuleParseError {
+                    message: "number of states must be from 0 through 255",
+                    reason: RuleParseReason::DigitParse
+                })?,
+                "M" => middle = match value {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(RuleParseError {
+                        message: "middle must be 0 or 1",
+                        reason: RuleParseReason::DigitParse
+                    }),
+                },
+                "S" => survive = Some(LtlRule::parse_range(value)?),
+                "B" => birth = Some(LtlRule::parse_range(value)?),
+                "N" => shape = match value {
+                    "M" => Shape::Moore,
+                    "N" => Shape::VonNeumann,
+                    _ => return Err(RuleParseError {
+                        message: "neighborhood must be 'NM' (moore) or 'NN' (von neumann)",
+                        reason: RuleParseReason::Format
+                    }),
+                },
+                _ => return Err(RuleParseError {
+                    message: "ltl rule fields must start with R, C, M, S, B or N",
+                    reason: RuleParseReason::Format
+                }),
+            }
+        }
+
+        let radius = match radius {
+            Some(radius @ 1..=16) => radius,
+            _ => return Err(RuleParseError {
+                message: "ltl rule must include a range from R1 through R16",
+                reason: RuleParseReason::Format
+            }),
+        };
+
+        match (survive, birth) {
+            (Some(survive), Some(birth)) => Ok(LtlRule {
+                radius,
+                states,
+                middle,
+                survive,
+                birth,
+                shape,
+            }),
+            _ => Err(RuleParseError {
+                message: "ltl rule must include a survival and birth range",
+                reason: RuleParseReason::Format
+            }),
+        }
+    }
+}
+
+impl fmt::Display for LtlRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shape = match self.shape {
+            Shape::VonNeumann => 'N',
+            _ => 'M',
+        };
+
+        write!(f, "R{},C{},M{},S{}..{},B{}..{},N{shape}",
+            self.radius,
+            self.states,
+            self.middle as u8,
+            self.survive.start(),
+            self.survive.end(),
+            self.birth.start(),
+            self.birth.end(),
+        )
+    }
+}
+
+pub struct LtlParams {
+    pub alive_ratio: f32,
+    pub rule: LtlRule,
+}
+
+impl Ltl {
+    /// Only live cells count towards their neighbors, not decaying ones.
+    fn weight(&self) -> u32 {
+        (self.state == 1) as u32
+    }
+
+    fn next_count(&self, params: &LtlParams, count: u32) -> Self {
+        let rule = &params.rule;
+        let states = rule.total_states();
+        let state = match self.state {
+            0 => rule.birth.contains(&count) as u8,
+            1 => {
+                let count = count + rule.middle as u32;
+                if rule.survive.contains(&count) { 1 } else { 2 % states }
+            },
+            state => (state + 1) % states,
+        };
+
+        Ltl { state }
+    }
+}
+
+impl Cell for Ltl {
+    type Params = LtlParams;
+    const NAME: &'static str = "ltl";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        Ltl {
+            state: rng.gen_bool(params.alive_ratio) as u8,
+        }
+    }
+
+    fn color(&self, params: &Self::Params) -> Color {
+        match self.state {
+            0 => WHITE,
+            1 => BLACK,
+            state => {
+                // fade from black towards white as the cell decays
+                let t = (state - 1) as f32 / (params.rule.total_states() - 1) as f32;
+                BLACK.lerp(WHITE, t)
+            },
+        }
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Counts {
+            weight: Ltl::weight,
+            next: Ltl::next_count,
+        }
+    }
+
+    fn neighborhood(params: &Self::Params) -> Option<Neighborhood> {
+        Some(Neighborhood::Shape(params.rule.shape, params.rule.radius))
+    }
+
+    fn paint_states(params: &Self::Params) -> Vec<Self> {
+        (1..params.rule.total_states())
+            .map(|state| Ltl { state })
+            .collect()
+    }
+
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> {
+        (state < params.rule.total_states()).then_some(Ltl { state })
+    }
+
+    fn state(&self) -> u8 {
+        self.state
+    }
+
+    fn rule(params: &Self::Params) -> Option<String> {
+        Some(params.rule.to_string())
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.alive_ratio);
+        writer.str(&params.rule.to_string());
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        Some(LtlParams {
+            alive_ratio: reader.f32()?,
+            rule: LtlRule::from_str(reader.str()?).ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::Automata;
+    use crate::automata::tests::{check_resume, params};
+
+    fn rule(s: &str) -> LtlRule {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_golly_notation() {
+        let bosco = rule("R5,C0,M1,S34..58,B34..45,NM");
+        assert_eq!(bosco.radius, 5);
+        assert_eq!(bosco.states, 0);
+        assert!(bosco.middle);
+        assert_eq!(bosco.survive, 34..=58);
+        assert_eq!(bosco.birth, 34..=45);
+        assert_eq!(bosco.shape, Shape::Moore);
+
+        // fields are case insensitive, and single numbers are ranges of one
+        let small = rule("r2, c3, m0, s4, b5..6, nn");
+        assert_eq!((small.radius, small.states, small.middle), (2, 3, false));
+        assert_eq!((small.survive, small.birth), (4..=4, 5..=6));
+        assert_eq!(small.shape, Shape::VonNeumann);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in ["R5,C0,M1,S34..58,B34..45,NM", "R2,C3,M0,S4..4,B5..6,NN"] {
+            assert_eq!(rule(s).to_string(), s);
+        }
+
+        assert_eq!(rule("R1,S2..3,B3").to_string(), "R1,C0,M0,S2..3,B3..3,NM");
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        let bad = [
+            "C0,M1,S34..58,B34..45",
+            "R0,S1,B1",
+            "R17,S1,B1",
+            "R1,C256,S1,B1",
+            "R1,M2,S1,B1",
+            "R1,S1,B1,NH",
+            "R1,S1,B1,X3",
+            "R1,B1",
+            "R1,S1",
+            "R1,S1..x,B1",
+        ];
+
+        for s in bad {
+            assert!(s.parse::<LtlRule>().is_err(), "{s} should not parse");
+        }
+    }
+
+    #[test]
+    fn counts_decide_the_next_state() {
+        let params = LtlParams {
+            alive_ratio: 0.0,
+            rule: rule("R1,C3,M1,S3..4,B3,NM"),
+        };
+
+        let next = |state, count| Ltl { state }.next_count(&params, count).state;
+
+        // births need exactly 3
+        assert_eq!(next(0, 3), 1);
+        assert_eq!(next(0, 4), 0);
+
+        // live cells count themselves, then start dying
+        assert_eq!(next(1, 2), 1);
+        assert_eq!(next(1, 3), 1);
+        assert_eq!(next(1, 4), 2);
+
+        // and dying cells keep dying whatever their neighbors
+        assert_eq!(next(2, 3), 0);
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        let automata = Automata::<Ltl>::new(params(30, 20), LtlParams {
+            alive_ratio: 0.5,
+            rule: rule("R2,C4,M0,S3..6,B4..5,NN"),
+        });
+
+        check_resume(automata, 10);
+    }
+}
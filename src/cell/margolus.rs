@@ -0,0 +1,250 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::cell::{Cell, UpdateMode};
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A cell in a block automata using the Margolus neighborhood, where the grid
+/// is split into 2x2 blocks that change together.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Margolus {
+    alive: bool,
+}
+
+/// What each 2x2 block becomes, in MCell's notation, e.g. the billiard ball
+/// machine "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15".
+///
+/// Blocks are numbered by adding 1 for the top left cell, 2 for the top
+/// right, 4 for the bottom left and 8 for the bottom right, and the table
+/// gives the next block for each of the 16 blocks.
+#[derive(Clone, Debug)]
+pub struct BlockRule {
+    table: [u8; 16],
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BlockRuleParseError {
+    pub message: &'static str,
+}
+
+impl BlockRule {
+    /// Named rules that can be used in place of a table.
+    const PRESETS: &[(&str, [u8; 16])] = &[
+        ("bbm", [0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15]),
+        ("critters", [15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0]),
+        ("tron", [15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0]),
+        ("sand", [0, 4, 8, 12, 4, 12, 12, 13, 8, 12, 12, 14, 12, 13, 14, 15]),
+    ];
+
+    /// The next block for a block of cells in the order top left, top right,
+    /// bottom left and bottom right.
+    pub fn next(&self, block: [bool; 4]) -> [bool; 4] {
+        let index = block.iter()
+            .enumerate()
+            .fold(0, |index, (corner, &alive)| index | (alive as usize) << corner);
+
+        let next = self.table[index];
+        [0, 1, 2, 3].map(|corner| next & (1 << corner) != 0)
+    }
+}
+
+impl FromStr for BlockRule {
+    type Err = BlockRuleParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        if let Some((_, table)) = BlockRule::PRESETS.iter().find(|(name, _)| *name == s) {
+            return Ok(BlockRule { table: *table });
+        }
+
+        // MCell also writes rules as "MS,D0;8;4;..."
+        let format_err = BlockRuleParseError {
+            message: "block rule must be a name or of the form 'M0,8,4,...' with 16 blocks",
+        };
+
+        let table = s.strip_prefix("ms,d")
+            .or_else(|| s.strip_prefix('m'))
+            .ok_or(format_err)?;
+
+        let table: Vec<u8> = table.split([',', ';'])
+            .map(|block| block.trim().parse().ok().filter(|&block| block < 16))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(BlockRuleParseError {
+                message: "blocks must be numbers from 0 through 15",
+            })?;
+
+        Ok(BlockRule {
+            table: table.try_into().map_err(|_| format_err)?,
+        })
+    }
+}
+
+impl fmt::Display for BlockRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let table: Vec<String> = self.table.iter()
+            .map(u8::to_string)
+            .collect();
+
+        write!(f, "M{}", table.join(","))
+    }
+}
+
+pub struct MargolusParams {
+    pub alive_ratio: f32,
+    pub rule: BlockRule,
+}
+
+impl Margolus {
+    fn next_block(params: &MargolusParams, block: [&Self; 4]) -> [Self; 4] {
+        params.rule.next(block.map(|cell| cell.alive))
+            .map(|alive| Margolus { alive })
+    }
+}
+
+impl Cell for Margolus {
+    type Params = MargolusParams;
+    const NAME: &'static str = "margolus";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        Margolus {
+            alive: rng.gen_bool(params.alive_ratio),
+        }
+    }
+
+    fn color(&self, _params: &Self::Params) -> Color {
+        match self.alive {
+            false => WHITE,
+            true => BLACK,
+        }
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Blocks(Margolus::next_block)
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        vec![Margolus { alive: true }]
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        match state {
+            0 | 1 => Some(Margolus { alive: state == 1 }),
+            _ => None,
+        }
+    }
+
+    fn state(&self) -> u8 {
+        self.alive as u8
+    }
+
+    fn rule(params: &Self::Params) -> Option<String> {
+        Some(params.rule.to_string())
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.alive_ratio);
+        writer.str(&params.rule.to_string());
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        Some(MargolusParams {
+            alive_ratio: reader.f32()?,
+            rule: BlockRule::from_str(reader.str()?).ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::{Automata, AutomataParams, AutomataTrait};
+    use crate::boundary::Boundary;
+    use crate::automata::tests::{check_resume, params};
+
+    fn table(s: &str) -> [u8; 16] {
+        BlockRule::from_str(s).unwrap().table
+    }
+
+    #[test]
+    fn presets_match_mcell() {
+        let published = [
+            ("bbm", "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15"),
+            ("critters", "MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0"),
+            ("tron", "MS,D15;1;2;3;4;5;6;7;8;9;10;11;12;13;14;0"),
+            ("sand", "MS,D0;4;8;12;4;12;12;13;8;12;12;14;12;13;14;15"),
+        ];
+
+        for (name, rule) in published {
+            assert_eq!(table(name), table(rule), "{name}");
+        }
+    }
+
+    #[test]
+    fn reversible_presets_are_permutations() {
+        for name in ["bbm", "critters", "tron"] {
+            let mut sorted = table(name);
+            sorted.sort();
+            assert_eq!(sorted, core::array::from_fn(|block| block as u8), "{name}");
+        }
+
+        // critters leaves blocks of two alone, and flips the rest
+        let critters = table("critters");
+        for block in 0..16u8 {
+            let next = critters[block as usize];
+            match block.count_ones() {
+                2 => assert_eq!(next, block),
+                alive => assert_eq!(next.count_ones(), 4 - alive),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_mcell_notation() {
+        let bbm = [0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15];
+        assert_eq!(table("M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15"), bbm);
+        assert_eq!(table(" ms,d0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15 "), bbm);
+        assert_eq!(table("BBM"), bbm);
+        assert_eq!(BlockRule { table: bbm }.to_string(), "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15");
+
+        let bad = [
+            "",
+            "gliders",
+            "0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15",
+            "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14",
+            "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15,0",
+            "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,16",
+            "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,x",
+        ];
+
+        for s in bad {
+            assert!(BlockRule::from_str(s).is_err(), "{s:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn billiard_balls_are_conserved() {
+        for boundary in [Boundary::Dead, Boundary::Torus, Boundary::Klein] {
+            let mut automata = Automata::<Margolus>::new(AutomataParams { boundary, ..params(40, 30) }, MargolusParams {
+                alive_ratio: 0.3,
+                rule: BlockRule::from_str("bbm").unwrap(),
+            });
+
+            let alive = |automata: &Automata<Margolus>| automata.to_pattern(None).cells().count();
+            let start = alive(&automata);
+            for _ in 0..20 {
+                automata.next();
+                assert_eq!(alive(&automata), start);
+            }
+        }
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        let automata = Automata::<Margolus>::new(params(41, 31), MargolusParams {
+            alive_ratio: 0.3,
+            rule: BlockRule::from_str("critters").unwrap(),
+        });
+
+        check_resume(automata, 10);
+    }
+}
@@ -1,13 +1,216 @@
 pub mod life;
 pub mod cyclic;
 pub mod brain;
+pub mod generations;
+pub mod ltl;
+pub mod wireworld;
+pub mod turmite;
+pub mod kernel;
+pub mod lenia;
+pub mod smoothlife;
+pub mod margolus;
+pub mod sand;
+pub mod forest;
+pub mod greenberg;
+pub mod hodgepodge;
+pub mod sandpile;
+pub mod colorlife;
+pub mod rps;
 pub mod prelude;
 
-use macroquad::color::Color;
+use std::iter::{Copied, Flatten};
+use std::slice;
+use crate::automata::GridSize;
+use crate::color::Color;
+use crate::neighborhood::Neighborhood;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
 
 pub trait Cell {
     type Params;
-    fn new(params: &Self::Params) -> Self;
-    fn next<'a>(&'a self, params: &Self::Params, neighbors: impl IntoIterator<Item = &'a Self>) -> Self;
+
+    /// The name of the cell type, used to identify it in snapshots.
+    const NAME: &'static str;
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self;
+
+    /// The cells of a new grid, in column-major order. By default, each cell
+    /// comes from `new`, but cell types that need larger structures to start
+    /// from can lay out the whole grid.
+    fn initial(params: &Self::Params, grid_size: GridSize, rng: &mut Rng) -> Vec<Self> where Self: Sized {
+        (0..grid_size.cols * grid_size.rows)
+            .map(|_| Self::new(params, rng))
+            .collect()
+    }
+
     fn color(&self, params: &Self::Params) -> Color;
+
+    /// How the automata calculates each generation of cells.
+    fn update_mode(params: &Self::Params) -> UpdateMode<Self> where Self: Sized;
+
+    /// The neighborhood to use instead of the one given to the automata, for
+    /// cell types whose rules include their own neighborhood.
+    fn neighborhood(_params: &Self::Params) -> Option<Neighborhood> where Self: Sized {
+        None
+    }
+
+    /// Whether the cell looks different from `other`, so that it counts as
+    /// changed and is redrawn. Cells with continuous values can ignore
+    /// changes too small to see.
+    fn differs(&self, other: &Self) -> bool where Self: PartialEq + Sized {
+        self != other
+    }
+
+    /// The states that can be painted onto the grid with the mouse.
+    fn paint_states(params: &Self::Params) -> Vec<Self> where Self: Sized;
+
+    /// The cell for a numbered state in a pattern file, where 0 is the
+    /// default state, or `None` if there is no such state.
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> where Self: Sized;
+
+    /// The numbered state of the cell in a pattern file.
+    fn state(&self) -> u8;
+
+    /// The rule written to the header of pattern files, if the cell type
+    /// has one.
+    fn rule(_params: &Self::Params) -> Option<String> where Self: Sized {
+        None
+    }
+
+    /// Writes the cell to a snapshot, as its pattern state by default.
+    fn write(&self, writer: &mut Writer) {
+        writer.u8(self.state());
+    }
+
+    /// Reads a cell written by `write`.
+    fn read(reader: &mut Reader, params: &Self::Params) -> Option<Self> where Self: Sized {
+        Self::from_state(reader.u8()?, params)
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) where Self: Sized;
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> where Self: Sized;
+}
+
+/// Calculates the next cell from its neighbors. `rng` is seeded for the
+/// cell and generation, so random rules give the same results however the
+/// grid is split between threads.
+pub type NextFn<C> = for<'a> fn(&'a C, &<C as Cell>::Params, Neighbors<'a, C>, &mut Rng) -> C;
+
+/// Calculates the next cell and the (column, row) offset of the neighbor it
+/// wants to swap places with.
+pub type MoveFn<C> = for<'a> fn(&'a C, &<C as Cell>::Params, Neighbors<'a, C>) -> (C, Option<(isize, isize)>);
+
+/// How the automata calculates each generation of a cell type. Each mode
+/// holds the functions it needs, so a cell type cannot pick a mode without
+/// implementing it.
+pub enum UpdateMode<C: Cell> {
+    /// Each cell is calculated from its neighbors.
+    Neighbors(NextFn<C>),
+
+    /// Cells topple like a sandpile: each cell is calculated by `next` from
+    /// its neighbors, and the cells that `topples` picks out are counted as
+    /// the size of the generation's avalanche.
+    ///
+    /// With `relax`, each generation instead starts by adding a grain to a
+    /// random cell with `add_grain`, then keeps going until no cells topple,
+    /// so that every generation is one whole avalanche. Grains must be able
+    /// to leave the grid for it to settle, so snapshots that relax on a
+    /// closed boundary are rejected, and a generation that still has not
+    /// settled after `RELAX_PASSES` passes carries on in the next one.
+    Topple {
+        next: NextFn<C>,
+        topples: fn(&C) -> bool,
+        add_grain: fn(&C) -> C,
+        relax: bool,
+    },
+
+    /// Each cell is calculated by `next` from the total `weight` of its
+    /// neighbors. The totals come from a summed-area table, which is much
+    /// faster for wide neighborhoods.
+    Counts {
+        weight: fn(&C) -> u32,
+        next: fn(&C, &C::Params, u32) -> C,
+    },
+
+    /// The grid is split into 2x2 blocks that change together, and the blocks
+    /// shift by one cell every other generation. Each block is calculated
+    /// once from its top left, top right, bottom left and bottom right
+    /// cells, and gives the next cells in the same order. Shifted blocks
+    /// would overlap where a grid wraps around, so wrapping grids need an
+    /// even number of columns and rows.
+    Blocks(fn(&C::Params, [&C; 4]) -> [C; 4]),
+
+    /// Cells move around the grid by swapping places with their neighbors.
+    /// When several cells want the same neighbor, the first of them in the
+    /// neighborhood wins, and a cell only swaps with a neighbor that is not
+    /// moving itself.
+    Moves(MoveFn<C>),
+
+    /// Cells never change by themselves, only when agents write to them, so
+    /// the automata only moves the agents.
+    Agents,
+}
+
+impl<C: Cell> Clone for UpdateMode<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: Cell> Copy for UpdateMode<C> {}
+
+/// The neighbors of a cell, in the order of the neighborhood offsets.
+///
+/// Iterating over the neighbors skips any that are outside of the grid, so
+/// cells that depend on where their neighbors are should use `get` or
+/// `with_offsets` instead.
+pub struct Neighbors<'a, C> {
+    offsets: &'a [(isize, isize)],
+    cells: &'a [Option<&'a C>],
+}
+
+impl<'a, C> Neighbors<'a, C> {
+    /// Pairs each (column, row) offset with the neighbor at that offset, or
+    /// `None` if it is outside of the grid.
+    pub fn new(offsets: &'a [(isize, isize)], cells: &'a [Option<&'a C>]) -> Neighbors<'a, C> {
+        Neighbors { offsets, cells }
+    }
+
+    /// The neighbor at a (column, row) offset from the cell, or `None` if the
+    /// offset is outside of the grid or not part of the neighborhood.
+    pub fn get(&self, dcol: isize, drow: isize) -> Option<&'a C> {
+        self.offsets.iter()
+            .position(|&offset| offset == (dcol, drow))
+            .and_then(|index| self.cells[index])
+    }
+
+    /// Every neighbor in the order of the offsets, with `None` for neighbors
+    /// outside of the grid.
+    pub fn as_slice(&self) -> &'a [Option<&'a C>] {
+        self.cells
+    }
+
+    /// Iterates over the neighbors inside of the grid with their offsets.
+    pub fn with_offsets(&self) -> impl Iterator<Item = ((isize, isize), &'a C)> + 'a {
+        self.offsets.iter()
+            .zip(self.cells.iter())
+            .filter_map(|(&offset, &cell)| Some((offset, cell?)))
+    }
+}
+
+impl<C> Clone for Neighbors<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Neighbors<'_, C> {}
+
+impl<'a, C> IntoIterator for Neighbors<'a, C> {
+    type Item = &'a C;
+    type IntoIter = Flatten<Copied<slice::Iter<'a, Option<&'a C>>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cells.iter().copied().flatten()
+    }
 }
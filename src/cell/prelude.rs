@@ -1,5 +1,19 @@
-pub use crate::cell::Cell;
+pub use crate::cell::{Cell, Neighbors, UpdateMode};
 pub use crate::cell::life::{Life, LifeParams};
 pub use crate::cell::cyclic::{Cyclic, CyclicParams};
 pub use crate::cell::cyclic::palette::*;
 pub use crate::cell::brain::{Brain, BrainParams};
+pub use crate::cell::generations::{Generations, GenerationsParams};
+pub use crate::cell::ltl::{Ltl, LtlParams};
+pub use crate::cell::wireworld::{Wireworld, WireworldParams};
+pub use crate::cell::turmite::{Turmite, TurmiteParams};
+pub use crate::cell::lenia::{Lenia, LeniaParams};
+pub use crate::cell::smoothlife::{SmoothLife, SmoothLifeParams};
+pub use crate::cell::margolus::{Margolus, MargolusParams};
+pub use crate::cell::sand::{Sand, SandParams};
+pub use crate::cell::forest::{Forest, ForestParams};
+pub use crate::cell::greenberg::{GreenbergHastings, GreenbergHastingsParams};
+pub use crate::cell::hodgepodge::{Hodgepodge, HodgepodgeParams};
+pub use crate::cell::sandpile::{Sandpile, SandpileParams};
+pub use crate::cell::colorlife::{ColorLife, ColorLifeParams};
+pub use crate::cell::rps::{RockPaperScissors, RockPaperScissorsParams};
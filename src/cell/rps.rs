@@ -0,0 +1,185 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::Color;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A cell in a rock-paper-scissors automata, where each species is eaten by
+/// the next one, and the last species is eaten by the first.
+///
+/// Unlike `Cyclic`, where cells only advance to the next value, the species
+/// stand for competing populations, with randomness to roughen the fronts
+/// between them.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct RockPaperScissors {
+    species: u8,
+}
+
+pub struct RockPaperScissorsParams {
+    /// The number of neighbors of the predator species that replace a cell
+    pub threshold: u8,
+    /// The most the threshold is raised by, chosen at random for each cell
+    /// and generation
+    pub randomness: u8,
+    /// The color of each species, so there is one species per color
+    pub palette: Vec<Color>,
+}
+
+impl RockPaperScissorsParams {
+    fn species(&self) -> u8 {
+        self.palette.len() as u8
+    }
+}
+
+impl RockPaperScissors {
+    fn next<'a>(&'a self, params: &RockPaperScissorsParams, neighbors: Neighbors<'a, Self>, rng: &mut Rng) -> Self {
+        let predator = (self.species + 1) % params.species();
+        let count = neighbors.into_iter()
+            .filter(|neighbor| neighbor.species == predator)
+            .count();
+
+        let threshold = params.threshold as usize + rng.gen_below(params.randomness as usize + 1);
+        match count >= threshold {
+            true => RockPaperScissors { species: predator },
+            false => self.clone(),
+        }
+    }
+}
+
+impl Cell for RockPaperScissors {
+    type Params = RockPaperScissorsParams;
+    const NAME: &'static str = "rps";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        RockPaperScissors {
+            species: rng.gen_below(params.species() as usize) as u8,
+        }
+    }
+
+    fn color(&self, params: &Self::Params) -> Color {
+        params.palette[self.species as usize]
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(RockPaperScissors::next)
+    }
+
+    fn paint_states(params: &Self::Params) -> Vec<Self> {
+        (0..params.species())
+            .map(|species| RockPaperScissors { species })
+            .collect()
+    }
+
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> {
+        (state < params.species()).then_some(RockPaperScissors { species: state })
+    }
+
+    fn state(&self) -> u8 {
+        self.species
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.u8(params.threshold);
+        writer.u8(params.randomness);
+        writer.u16(params.palette.len() as u16);
+        for color in params.palette.iter() {
+            for channel in [color.r, color.g, color.b, color.a] {
+                writer.f32(channel);
+            }
+        }
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        let threshold = reader.u8()?;
+        let randomness = reader.u8()?;
+        let len = reader.u16()? as usize;
+        let palette = (0..len)
+            .map(|_| Some(Color::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?)))
+            .collect::<Option<Vec<Color>>>()
+            .filter(|palette| (2..=255).contains(&palette.len()))?;
+
+        Some(RockPaperScissorsParams { threshold, randomness, palette })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::{Automata, AutomataParams, AutomataTrait};
+    use crate::automata::tests::{check_resume, params};
+    use crate::color::BLACK;
+    use crate::neighborhood::{Neighborhood, Shape};
+
+    fn rps(threshold: u8, randomness: u8) -> RockPaperScissorsParams {
+        RockPaperScissorsParams {
+            threshold,
+            randomness,
+            palette: vec![BLACK; 3],
+        }
+    }
+
+    /// The next species of a cell of `species` with neighbors of the
+    /// `neighbors` species, using `rng` for the threshold.
+    fn next(params: &RockPaperScissorsParams, species: u8, neighbors: &[u8], rng: &mut Rng) -> u8 {
+        let offsets = Neighborhood::Shape(Shape::Moore, 1).offsets();
+        let cells: Vec<RockPaperScissors> = neighbors.iter()
+            .map(|&species| RockPaperScissors { species })
+            .collect();
+
+        let mut neighbors: Vec<Option<&RockPaperScissors>> = vec![None; offsets.len()];
+        for (neighbor, cell) in neighbors.iter_mut().zip(cells.iter()) {
+            *neighbor = Some(cell);
+        }
+
+        let cell = RockPaperScissors { species };
+        cell.next(params, Neighbors::new(&offsets, &neighbors), rng).species
+    }
+
+    #[test]
+    fn predators_replace_cells_at_the_threshold() {
+        let params = rps(3, 0);
+        let mut rng = Rng::new(1);
+        assert_eq!(next(&params, 0, &[1, 1], &mut rng), 0);
+        assert_eq!(next(&params, 0, &[1, 1, 1], &mut rng), 1);
+        assert_eq!(next(&params, 1, &[2, 2, 2, 2], &mut rng), 2);
+
+        // the last species is eaten by the first
+        assert_eq!(next(&params, 2, &[0, 0, 0], &mut rng), 0);
+    }
+
+    #[test]
+    fn cells_without_predators_stay() {
+        // prey and cells of the same species do not count
+        let params = rps(1, 0);
+        let mut rng = Rng::new(1);
+        assert_eq!(next(&params, 0, &[], &mut rng), 0);
+        assert_eq!(next(&params, 0, &[0, 0, 2, 2, 2, 2, 2, 2], &mut rng), 0);
+    }
+
+    #[test]
+    fn random_thresholds_depend_only_on_the_seed() {
+        // four predators are enough for a threshold of 3 or 4, but not 5
+        let params = rps(3, 2);
+        let outcomes: Vec<u8> = (0..64)
+            .map(|seed| next(&params, 0, &[1, 1, 1, 1], &mut Rng::new(seed)))
+            .collect();
+
+        assert!(outcomes.contains(&0) && outcomes.contains(&1));
+        for (seed, &outcome) in (0..64).zip(outcomes.iter()) {
+            assert_eq!(next(&params, 0, &[1, 1, 1, 1], &mut Rng::new(seed)), outcome);
+        }
+    }
+
+    #[test]
+    fn random_runs_do_not_depend_on_the_split() {
+        let automata = |params: AutomataParams| Automata::<RockPaperScissors>::new(params, rps(3, 2));
+        let mut single = automata(AutomataParams { threads: 1, chunks: 1, ..params(40, 30) });
+        let mut split = automata(params(40, 30));
+        for _ in 0..10 {
+            single.next();
+            split.next();
+        }
+
+        assert_eq!(single.save_snapshot(), split.save_snapshot());
+        check_resume(automata(params(40, 30)), 10);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::*;
+use crate::neighborhood::{Mask, Neighborhood};
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Material {
+    #[default]
+    Empty,
+    Sand,
+    Water,
+    Wall,
+}
+
+/// A cell in a falling sand simulation. Sand falls and piles up, water falls
+/// and spreads out sideways, and walls never move.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Sand {
+    material: Material,
+    /// Which way the cell slides first, so that piles are not lopsided.
+    /// Water turns around when it cannot move.
+    left: bool,
+}
+
+pub struct SandParams {
+    pub sand_ratio: f32,
+    pub water_ratio: f32,
+}
+
+impl Sand {
+    /// Where sand and water look to move, in order.
+    fn directions(&self) -> &'static [(isize, isize)] {
+        match (self.material, self.left) {
+            (Material::Sand, true) => &[(0, 1), (-1, 1), (1, 1)],
+            (Material::Sand, false) => &[(0, 1), (1, 1), (-1, 1)],
+            (Material::Water, true) => &[(0, 1), (-1, 1), (1, 1), (-1, 0)],
+            (Material::Water, false) => &[(0, 1), (1, 1), (-1, 1), (1, 0)],
+            _ => &[],
+        }
+    }
+
+    /// Whether the cell can swap places with `other`. Sand sinks through
+    /// water, and both fall into empty cells.
+    fn displaces(&self, other: &Sand) -> bool {
+        match self.material {
+            Material::Sand => matches!(other.material, Material::Empty | Material::Water),
+            Material::Water => other.material == Material::Empty,
+            _ => false,
+        }
+    }
+
+    fn next_move<'a>(&'a self, _params: &SandParams, neighbors: Neighbors<'a, Self>) -> (Self, Option<(isize, isize)>) {
+        let target = self.directions().iter()
+            .copied()
+            .find(|&(dcol, drow)| neighbors.get(dcol, drow).is_some_and(|neighbor| self.displaces(neighbor)));
+
+        match (target, self.material) {
+            (None, Material::Water) => (Sand { left: !self.left, ..self.clone() }, None),
+            _ => (self.clone(), target),
+        }
+    }
+}
+
+impl Cell for Sand {
+    type Params = SandParams;
+    const NAME: &'static str = "sand";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        let roll = rng.gen_f32();
+        let material = if roll < params.sand_ratio {
+            Material::Sand
+        }
+        else if roll < params.sand_ratio + params.water_ratio {
+            Material::Water
+        }
+        else {
+            Material::Empty
+        };
+
+        Sand {
+            material,
+            left: rng.gen_bool(0.5),
+        }
+    }
+
+    fn color(&self, _params: &Self::Params) -> Color {
+        match self.material {
+            Material::Empty => BLACK,
+            Material::Sand => GOLD,
+            Material::Water => BLUE,
+            Material::Wall => GRAY,
+        }
+    }
+
+    /// Every neighbor, with the cells above first so that falling cells win
+    /// over cells sliding sideways.
+    fn neighborhood(_params: &Self::Params) -> Option<Neighborhood> {
+        let offsets = [(0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (-1, 1), (1, 1)];
+        Some(Neighborhood::Custom(Mask::from_offsets(offsets.into())))
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Moves(Sand::next_move)
+    }
+
+    /// Water turning around does not count as a change.
+    fn differs(&self, other: &Self) -> bool {
+        self.material != other.material
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        [Material::Sand, Material::Water, Material::Wall]
+            .map(|material| Sand { material, left: false })
+            .into()
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        let material = match state {
+            0 => Material::Empty,
+            1 => Material::Sand,
+            2 => Material::Water,
+            3 => Material::Wall,
+            _ => return None,
+        };
+
+        Some(Sand { material, left: false })
+    }
+
+    fn state(&self) -> u8 {
+        self.material as u8
+    }
+
+    /// Writes the material, with the direction in the top bit.
+    fn write(&self, writer: &mut Writer) {
+        writer.u8(self.state() | (self.left as u8) << 7);
+    }
+
+    fn read(reader: &mut Reader, params: &Self::Params) -> Option<Self> {
+        let value = reader.u8()?;
+        let cell = Sand::from_state(value & 0x7f, params)?;
+        Some(Sand { left: value & 0x80 != 0, ..cell })
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.sand_ratio);
+        writer.f32(params.water_ratio);
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        Some(SandParams {
+            sand_ratio: reader.f32()?,
+            water_ratio: reader.f32()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::automata::{Automata, AutomataParams, AutomataTrait};
+    use crate::automata::tests::{check_resume, params};
+    use crate::boundary::Boundary;
+    use crate::pattern::Pattern;
+
+    fn sand(params: AutomataParams) -> Automata<Sand> {
+        Automata::new(params, SandParams {
+            sand_ratio: 0.3,
+            water_ratio: 0.3,
+        })
+    }
+
+    fn materials(automata: &Automata<Sand>) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for (_, _, state) in automata.to_pattern(None).cells() {
+            counts[state as usize] += 1;
+        }
+
+        counts
+    }
+
+    #[test]
+    fn sand_falls_and_sinks_through_water() {
+        // a grain of sand over water, with walls on either side
+        let mut automata = sand(params(3, 4));
+        let pattern = Pattern::from_str("x = 3, y = 4\nCAC$CBC$C.C$CCC!").unwrap();
+        automata.load_pattern(&pattern, None).unwrap();
+
+        // the water falls first, since sand only swaps with cells that stay
+        // put, then the sand follows and sinks through it
+        let frames = ["CAC$C.C$CBC$3C!", "C.C$CAC$CBC$3C!", "C.C$CBC$CAC$3C!"];
+        for frame in frames {
+            automata.next();
+            assert_eq!(automata.to_pattern(None).to_rle(), format!("x = 3, y = 4\n{frame}\n"));
+        }
+
+        // then nothing can move
+        automata.next();
+        assert_eq!(automata.changed(), 0);
+    }
+
+    #[test]
+    fn materials_are_conserved() {
+        for boundary in [Boundary::Dead, Boundary::Torus, Boundary::Mirror] {
+            let mut automata = sand(AutomataParams { boundary, ..params(30, 20) });
+            let start = materials(&automata);
+            for _ in 0..30 {
+                automata.next();
+                assert_eq!(materials(&automata), start);
+            }
+        }
+    }
+
+    #[test]
+    fn threads_and_chunks_do_not_change_the_run() {
+        let mut single = sand(AutomataParams { threads: 1, chunks: 1, ..params(30, 20) });
+        let mut split = sand(params(30, 20));
+        for _ in 0..30 {
+            single.next();
+            split.next();
+            assert_eq!(single.changed(), split.changed());
+        }
+
+        assert_eq!(single.save_snapshot(), split.save_snapshot());
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        check_resume(sand(params(30, 20)), 15);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::automata::GridSize;
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::Color;
+use crate::neighborhood::{Neighborhood, Shape};
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// The number of grains that makes a cell topple, one for each neighbor.
+const THRESHOLD: u32 = 4;
+
+/// A cell in the Abelian sandpile model, holding a number of grains of sand.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Sandpile {
+    grains: u32,
+}
+
+pub struct SandpileParams {
+    /// The number of grains in the pile at the center of the grid
+    pub pile: u32,
+    /// Whether each generation adds a grain to a random cell and relaxes to a
+    /// stable state, instead of every cell toppling at most once
+    pub relax: bool,
+    /// The color of cells with 0 through 3 grains, then of cells about to
+    /// topple
+    pub palette: Vec<Color>,
+}
+
+impl Sandpile {
+    fn topples(&self) -> bool {
+        self.grains >= THRESHOLD
+    }
+
+    fn add_grain(&self) -> Self {
+        Sandpile {
+            grains: self.grains.saturating_add(1),
+        }
+    }
+
+    /// Cells with enough grains give one to each neighbor, and grains that
+    /// fall off the edge of the grid are lost.
+    fn next<'a>(&'a self, _params: &SandpileParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        let received = neighbors.into_iter()
+            .filter(|neighbor| neighbor.topples())
+            .count() as u32;
+
+        let kept = match self.topples() {
+            true => self.grains - THRESHOLD,
+            false => self.grains,
+        };
+
+        Sandpile {
+            grains: kept + received,
+        }
+    }
+}
+
+impl Cell for Sandpile {
+    type Params = SandpileParams;
+    const NAME: &'static str = "sandpile";
+
+    fn new(_params: &Self::Params, _rng: &mut Rng) -> Self {
+        Sandpile::default()
+    }
+
+    /// Starts with every grain in one pile at the center of the grid.
+    fn initial(params: &Self::Params, grid_size: GridSize, _rng: &mut Rng) -> Vec<Self> {
+        let GridSize { cols, rows } = grid_size;
+        let mut cells = vec![Sandpile::default(); cols * rows];
+        cells[cols / 2 * rows + rows / 2].grains = params.pile;
+        cells
+    }
+
+    fn color(&self, params: &Self::Params) -> Color {
+        params.palette[self.grains.min(THRESHOLD) as usize]
+    }
+
+    fn update_mode(params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Topple {
+            next: Sandpile::next,
+            topples: Sandpile::topples,
+            add_grain: Sandpile::add_grain,
+            relax: params.relax,
+        }
+    }
+
+    fn neighborhood(_params: &Self::Params) -> Option<Neighborhood> {
+        Some(Neighborhood::Shape(Shape::VonNeumann, 1))
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        (1..=THRESHOLD)
+            .map(|grains| Sandpile { grains })
+            .collect()
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        Some(Sandpile {
+            grains: state as u32,
+        })
+    }
+
+    fn state(&self) -> u8 {
+        self.grains.min(u8::MAX as u32) as u8
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.u64(self.grains as u64);
+    }
+
+    fn read(reader: &mut Reader, _params: &Self::Params) -> Option<Self> {
+        Some(Sandpile {
+            grains: reader.u64()?.try_into().ok()?,
+        })
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.u64(params.pile as u64);
+        writer.u8(params.relax as u8);
+        writer.u16(params.palette.len() as u16);
+        for color in params.palette.iter() {
+            for channel in [color.r, color.g, color.b, color.a] {
+                writer.f32(channel);
+            }
+        }
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        let pile = reader.u64()?.try_into().ok()?;
+        let relax = reader.u8()? != 0;
+        let len = reader.u16()? as usize;
+        let palette = (0..len)
+            .map(|_| Some(Color::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?)))
+            .collect::<Option<Vec<Color>>>()
+            .filter(|palette| palette.len() > THRESHOLD as usize)?;
+
+        Some(SandpileParams { pile, relax, palette })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::{Automata, AutomataParams, AutomataTrait};
+    use crate::automata::tests::{check_resume, params};
+    use crate::boundary::Boundary;
+    use crate::color::BLACK;
+    use crate::snapshot;
+
+    fn sandpile(params: AutomataParams, pile: u32, relax: bool) -> Automata<Sandpile> {
+        Automata::new(params, SandpileParams {
+            pile,
+            relax,
+            palette: vec![BLACK; 5],
+        })
+    }
+
+    /// The grains in every cell that has any.
+    fn grains(automata: &Automata<Sandpile>) -> Vec<u32> {
+        automata.to_pattern(None).cells()
+            .map(|(_, _, state)| state as u32)
+            .collect()
+    }
+
+    #[test]
+    fn counts_topplings_not_changed_cells() {
+        // a pile of 4 topples once, changing itself and its 4 neighbors
+        let mut automata = sandpile(params(5, 5), 4, false);
+        automata.next();
+        assert_eq!(automata.topplings(), Some(1));
+        assert_eq!(automata.changed(), 5);
+
+        automata.next();
+        assert_eq!(automata.topplings(), Some(0));
+    }
+
+    #[test]
+    fn relaxing_adds_a_grain_then_settles_each_generation() {
+        let mut automata = sandpile(params(7, 7), 0, true);
+        let mut avalanches = 0;
+        for generation in 1..=200 {
+            automata.next();
+            let grains = grains(&automata);
+            assert!(grains.iter().all(|&grains| grains < THRESHOLD));
+
+            // one grain is added each generation, and avalanches can only
+            // lose grains over the edge
+            assert!(grains.iter().sum::<u32>() <= generation);
+            if automata.topplings() != Some(0) {
+                avalanches += 1;
+            }
+        }
+
+        // 200 grains do not fit in 7x7 stable cells, so some must have toppled
+        assert!(avalanches > 0);
+    }
+
+    #[test]
+    fn rejects_relaxing_snapshots_on_closed_boundaries() {
+        for boundary in [Boundary::Torus, Boundary::Klein, Boundary::Mirror] {
+            let automata = sandpile(AutomataParams { boundary, ..params(5, 5) }, 3, true);
+            let bytes = automata.save_snapshot();
+            let (_, mut reader) = snapshot::read_header(&bytes).unwrap();
+            let err = Automata::<Sandpile>::from_snapshot(&mut reader, 1, 1).err().map(|err| err.message);
+            assert_eq!(err, Some("snapshot relaxes on a boundary that grains cannot leave"));
+        }
+
+        // without relaxing, every boundary is fine
+        let automata = sandpile(AutomataParams { boundary: Boundary::Torus, ..params(5, 5) }, 3, false);
+        let bytes = automata.save_snapshot();
+        let (_, mut reader) = snapshot::read_header(&bytes).unwrap();
+        assert!(Automata::<Sandpile>::from_snapshot(&mut reader, 1, 1).is_ok());
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        check_resume(sandpile(params(21, 15), 300, false), 20);
+        check_resume(sandpile(params(21, 15), 300, true), 20);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::cell::kernel::Kernel;
+use crate::cell::lenia::LENIA_COLORS;
+use crate::automata::GridSize;
+use crate::color::*;
+use crate::neighborhood::Neighborhood;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A cell in a SmoothLife automata, with a value from 0 to 1.
+#[derive(Clone, Default, PartialEq)]
+pub struct SmoothLife {
+    value: f32,
+}
+
+pub struct SmoothLifeParams {
+    pub alive_ratio: f32,
+    /// The outer radius of the neighborhood in cells, three times the radius
+    /// of the inner disk
+    pub range: usize,
+    /// The ranges of the outer filling for birth and survival
+    pub birth: (f32, f32),
+    pub survive: (f32, f32),
+    /// The smoothness of the steps for the outer and inner filling
+    pub alpha_n: f32,
+    pub alpha_m: f32,
+    /// The time step, where 1 is a step straight to the new value
+    pub dt: f32,
+    kernel: Kernel,
+}
+
+impl SmoothLifeParams {
+    pub fn new(alive_ratio: f32, range: usize, birth: (f32, f32), survive: (f32, f32), alpha_n: f32, alpha_m: f32, dt: f32) -> SmoothLifeParams {
+        // the disk and ring have antialiased edges, half a cell wide
+        let outer = range as f32;
+        let inner = outer / 3.0;
+        let edge = |radius: f32, distance: f32| (radius + 0.5 - distance).clamp(0.0, 1.0);
+        let disk = |distance: f32| edge(inner, distance);
+        let ring = |distance: f32| edge(outer, distance) * (1.0 - edge(inner, distance));
+
+        let kernel = Kernel::new(range, &[1.0, 0.0], &[&disk, &ring]);
+        SmoothLifeParams { alive_ratio, range, birth, survive, alpha_n, alpha_m, dt, kernel }
+    }
+
+    /// Rafler's transition function for the outer filling `n` and inner
+    /// filling `m`.
+    fn transition(&self, n: f32, m: f32) -> f32 {
+        let step = |x: f32, a: f32, alpha: f32| 1.0 / (1.0 + (-(x - a) * 4.0 / alpha).exp());
+        let interval = |x: f32, a: f32, b: f32| step(x, a, self.alpha_n) * (1.0 - step(x, b, self.alpha_n));
+        let mix = |x: f32, y: f32| {
+            let alive = step(m, 0.5, self.alpha_m);
+            x * (1.0 - alive) + y * alive
+        };
+
+        interval(n, mix(self.birth.0, self.survive.0), mix(self.birth.1, self.survive.1))
+    }
+}
+
+impl SmoothLife {
+    fn next<'a>(&'a self, params: &SmoothLifeParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        let m = params.kernel.sum(0, self, neighbors, |cell| cell.value);
+        let n = params.kernel.sum(1, self, neighbors, |cell| cell.value);
+        let target = params.transition(n, m);
+        let value = self.value + params.dt * (target - self.value);
+        SmoothLife {
+            value: value.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Cell for SmoothLife {
+    type Params = SmoothLifeParams;
+    const NAME: &'static str = "smoothlife";
+
+    fn new(params: &Self::Params, rng: &mut Rng) -> Self {
+        let value = match rng.gen_bool(params.alive_ratio) {
+            true => 1.0,
+            false => 0.0,
+        };
+
+        SmoothLife { value }
+    }
+
+    /// Noise averages out over the wide neighborhood and dies, so start from
+    /// random filled disks covering about `alive_ratio` of the grid instead.
+    fn initial(params: &Self::Params, grid_size: GridSize, rng: &mut Rng) -> Vec<Self> {
+        let GridSize { cols, rows } = grid_size;
+        let mut cells = vec![SmoothLife::default(); cols * rows];
+        let target = (params.alive_ratio * (cols * rows) as f32) as usize;
+        let (inner, outer) = (params.range / 3, params.range);
+
+        let mut filled = 0;
+        for _ in 0..cols * rows {
+            if filled >= target {
+                break;
+            }
+
+            let (center_col, center_row) = (rng.gen_below(cols) as isize, rng.gen_below(rows) as isize);
+            let radius = (inner + rng.gen_below(outer - inner + 1)) as isize;
+            for dcol in -radius..=radius {
+                for drow in -radius..=radius {
+                    let (col, row) = (center_col + dcol, center_row + drow);
+                    let inside = dcol * dcol + drow * drow <= radius * radius;
+                    if !inside || col < 0 || row < 0 || col >= cols as isize || row >= rows as isize {
+                        continue;
+                    }
+
+                    let cell = &mut cells[col as usize * rows + row as usize];
+                    if cell.value == 0.0 {
+                        cell.value = 1.0;
+                        filled += 1;
+                    }
+                }
+            }
+        }
+
+        cells
+    }
+
+    fn color(&self, _params: &Self::Params) -> Color {
+        Color::gradient(LENIA_COLORS, self.value)
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(SmoothLife::next)
+    }
+
+    fn neighborhood(params: &Self::Params) -> Option<Neighborhood> {
+        Some(params.kernel.neighborhood())
+    }
+
+    fn differs(&self, other: &Self) -> bool {
+        self.state() != other.state()
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        [1.0, 0.75, 0.5, 0.25].into_iter()
+            .map(|value| SmoothLife { value })
+            .collect()
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        Some(SmoothLife {
+            value: state as f32 / 255.0,
+        })
+    }
+
+    /// The value rounded to one of 256 levels.
+    fn state(&self) -> u8 {
+        (self.value * 255.0).round() as u8
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.f32(self.value);
+    }
+
+    fn read(reader: &mut Reader, _params: &Self::Params) -> Option<Self> {
+        let value = reader.f32()?;
+        (0.0..=1.0).contains(&value).then_some(SmoothLife { value })
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.f32(params.alive_ratio);
+        writer.u64(params.range as u64);
+        for value in [params.birth.0, params.birth.1, params.survive.0, params.survive.1] {
+            writer.f32(value);
+        }
+
+        writer.f32(params.alpha_n);
+        writer.f32(params.alpha_m);
+        writer.f32(params.dt);
+    }
+
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        let alive_ratio = reader.f32()?;
+        let range = reader.u64()? as usize;
+        if !(3..=32).contains(&range) {
+            return None;
+        }
+
+        let birth = (reader.f32()?, reader.f32()?);
+        let survive = (reader.f32()?, reader.f32()?);
+        let (alpha_n, alpha_m, dt) = (reader.f32()?, reader.f32()?, reader.f32()?);
+        let smooth = |alpha: f32| alpha.is_finite() && alpha > 0.0;
+        if !(smooth(alpha_n) && smooth(alpha_m) && dt > 0.0 && dt <= 1.0) {
+            return None;
+        }
+
+        Some(SmoothLifeParams::new(alive_ratio, range, birth, survive, alpha_n, alpha_m, dt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::Automata;
+    use crate::automata::tests::{check_resume, params};
+
+    fn params_with(alpha_n: f32, alpha_m: f32, dt: f32) -> SmoothLifeParams {
+        SmoothLifeParams::new(0.2, 6, (0.278, 0.365), (0.267, 0.445), alpha_n, alpha_m, dt)
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        check_resume(Automata::<SmoothLife>::new(params(30, 20), params_with(0.028, 0.147, 0.5)), 10);
+    }
+
+    #[test]
+    fn rejects_snapshots_with_bad_smoothness_or_steps() {
+        let read = |params: SmoothLifeParams| {
+            let mut writer = Writer::new();
+            SmoothLife::write_params(&params, &mut writer);
+            SmoothLife::read_params(&mut Reader::new(&writer.into_bytes())).is_some()
+        };
+
+        assert!(read(params_with(0.028, 0.147, 1.0)));
+        for dt in [0.0, -0.5, 1.5, f32::NAN] {
+            assert!(!read(params_with(0.028, 0.147, dt)), "dt {dt} should not load");
+        }
+
+        for (alpha_n, alpha_m) in [(0.0, 0.147), (0.028, 0.0), (-0.1, 0.147), (0.028, f32::INFINITY)] {
+            assert!(!read(params_with(alpha_n, alpha_m, 1.0)), "alpha {alpha_n}, {alpha_m} should not load");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::cell::{Cell, UpdateMode};
+use crate::cell::cyclic::palette;
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+/// A colored cell for turmites to walk on. The cells never change by
+/// themselves, only when a turmite leaves them.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Turmite {
+    color: u8,
+}
+
+pub struct TurmiteParams {
+    /// The color of each cell state, starting with the empty state
+    pub palette: Vec<Color>,
+}
+
+impl TurmiteParams {
+    /// Fades from white to black over the given number of colors.
+    pub fn grayscale(colors: u8) -> TurmiteParams {
+        TurmiteParams {
+            palette: palette::fade(colors as usize),
+        }
+    }
+
+    /// Starts with white, then cycles through the given colors.
+    pub fn from_colors(colors: u8, cycle: &[Color]) -> TurmiteParams {
+        TurmiteParams {
+            palette: palette::cycle(colors as usize, cycle),
+        }
+    }
+}
+
+impl Cell for Turmite {
+    type Params = TurmiteParams;
+    const NAME: &'static str = "turmite";
+
+    fn new(_params: &Self::Params, _rng: &mut Rng) -> Self {
+        Turmite::default()
+    }
+
+    fn color(&self, params: &Self::Params) -> Color {
+        params.palette[self.color as usize]
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Agents
+    }
+
+    fn paint_states(params: &Self::Params) -> Vec<Self> {
+        (1..params.palette.len() as u8)
+            .map(|color| Turmite { color })
+            .collect()
+    }
+
+    fn from_state(state: u8, params: &Self::Params) -> Option<Self> {
+        ((state as usize) < params.palette.len()).then_some(Turmite { color: state })
+    }
+
+    fn state(&self) -> u8 {
+        self.color
+    }
+
+    fn write_params(params: &Self::Params, writer: &mut Writer) {
+        writer.u16(params.palette.len() as u16);
+        for color in params.palette.iter() {
+            for channel in [color.r, color.g, color.b, color.a] {
+                writer.f32(channel);
+            }
+        }
+    }
+
+    /// Reads a palette with at least two colors, for the empty state and at
+    /// least one color for turmites to paint.
+    fn read_params(reader: &mut Reader) -> Option<Self::Params> {
+        let len = reader.u16()? as usize;
+        if len < 2 {
+            return None;
+        }
+
+        let palette = (0..len)
+            .map(|_| Some(Color::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?)))
+            .collect::<Option<Vec<Color>>>()?;
+
+        Some(TurmiteParams { palette })
+    }
+}
@@ -0,0 +1,163 @@
+use crate::cell::{Cell, Neighbors, UpdateMode};
+use crate::color::*;
+use crate::rng::Rng;
+use crate::snapshot::{Reader, Writer};
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub enum Wireworld {
+    #[default]
+    Empty,
+    Head,
+    Tail,
+    Conductor,
+}
+
+/// Wireworld has no options, and always starts with an empty grid to draw
+/// circuits on.
+pub struct WireworldParams;
+
+impl Wireworld {
+    fn next<'a>(&'a self, _params: &WireworldParams, neighbors: Neighbors<'a, Self>, _rng: &mut Rng) -> Self {
+        match *self {
+            Wireworld::Empty => Wireworld::Empty,
+            Wireworld::Head => Wireworld::Tail,
+            Wireworld::Tail => Wireworld::Conductor,
+            Wireworld::Conductor => {
+                let count = neighbors.into_iter()
+                    .filter(|neighbor| **neighbor == Wireworld::Head)
+                    .count();
+
+                match count {
+                    1 | 2 => Wireworld::Head,
+                    _ => Wireworld::Conductor,
+                }
+            },
+        }
+    }
+}
+
+impl Cell for Wireworld {
+    type Params = WireworldParams;
+    const NAME: &'static str = "wireworld";
+
+    fn new(_params: &Self::Params, _rng: &mut Rng) -> Self {
+        Wireworld::Empty
+    }
+
+    fn color(&self, _params: &Self::Params) -> Color {
+        match *self {
+            Wireworld::Empty => BLACK,
+            Wireworld::Head => BLUE,
+            Wireworld::Tail => RED,
+            Wireworld::Conductor => YELLOW,
+        }
+    }
+
+    fn update_mode(_params: &Self::Params) -> UpdateMode<Self> {
+        UpdateMode::Neighbors(Wireworld::next)
+    }
+
+    fn paint_states(_params: &Self::Params) -> Vec<Self> {
+        vec![Wireworld::Conductor, Wireworld::Head, Wireworld::Tail]
+    }
+
+    fn from_state(state: u8, _params: &Self::Params) -> Option<Self> {
+        match state {
+            0 => Some(Wireworld::Empty),
+            1 => Some(Wireworld::Head),
+            2 => Some(Wireworld::Tail),
+            3 => Some(Wireworld::Conductor),
+            _ => None,
+        }
+    }
+
+    fn state(&self) -> u8 {
+        match *self {
+            Wireworld::Empty => 0,
+            Wireworld::Head => 1,
+            Wireworld::Tail => 2,
+            Wireworld::Conductor => 3,
+        }
+    }
+
+    fn rule(_params: &Self::Params) -> Option<String> {
+        Some("WireWorld".to_owned())
+    }
+
+    fn write_params(_params: &Self::Params, _writer: &mut Writer) {}
+
+    fn read_params(_reader: &mut Reader) -> Option<Self::Params> {
+        Some(WireworldParams)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::automata::{Automata, AutomataTrait};
+    use crate::automata::tests::{check_resume, params};
+    use crate::neighborhood::{Neighborhood, Shape};
+    use crate::pattern::Pattern;
+
+    /// The next state of `cell` with `heads` of its neighbors as electron
+    /// heads and the rest as conductors.
+    fn next(cell: Wireworld, heads: usize) -> Wireworld {
+        let offsets = Neighborhood::Shape(Shape::Moore, 1).offsets();
+        let neighbors: Vec<Option<&Wireworld>> = (0..offsets.len())
+            .map(|n| match n < heads {
+                true => Some(&Wireworld::Head),
+                false => Some(&Wireworld::Conductor),
+            })
+            .collect();
+
+        cell.next(&WireworldParams, Neighbors::new(&offsets, &neighbors), &mut Rng::new(0))
+    }
+
+    /// Runs a circuit for a number of generations, returning whether an
+    /// electron head ever reached the cell at `col`, `row`.
+    fn reaches(rle: &str, generations: usize, col: usize, row: usize) -> bool {
+        let mut automata = Automata::<Wireworld>::new(params(12, 3), WireworldParams);
+        automata.load_pattern(&Pattern::from_str(rle).unwrap(), None).unwrap();
+        (0..generations).any(|_| {
+            automata.next();
+            automata.to_pattern(None).cells().any(|cell| cell == (col, row, 1))
+        })
+    }
+
+    #[test]
+    fn electrons_leave_tails_behind_them() {
+        assert!(next(Wireworld::Head, 0) == Wireworld::Tail);
+        assert!(next(Wireworld::Tail, 0) == Wireworld::Conductor);
+        assert!(next(Wireworld::Empty, 2) == Wireworld::Empty);
+    }
+
+    #[test]
+    fn conductors_carry_one_or_two_heads() {
+        assert!(next(Wireworld::Conductor, 0) == Wireworld::Conductor);
+        assert!(next(Wireworld::Conductor, 1) == Wireworld::Head);
+        assert!(next(Wireworld::Conductor, 2) == Wireworld::Head);
+        assert!(next(Wireworld::Conductor, 3) == Wireworld::Conductor);
+    }
+
+    #[test]
+    fn diodes_only_pass_electrons_one_way() {
+        // an electron heading right passes through the gap in the wire
+        let forward = "x = 12, y = 3\n3.2C7.$BA2C.7C$3.2C7.!";
+        assert!(reaches(forward, 12, 11, 1));
+
+        // but one heading left reaches the gap with three heads around it,
+        // too many for the wire past it
+        let backward = "x = 12, y = 3\n3.2C7.$4C.5CAB$3.2C7.!";
+        assert!(!reaches(backward, 12, 2, 1));
+        assert!(reaches(backward, 12, 5, 1));
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        let mut automata = Automata::<Wireworld>::new(params(12, 3), WireworldParams);
+        let forward = Pattern::from_str("x = 12, y = 3\n3.2C7.$BA2C.7C$3.2C7.!").unwrap();
+        automata.load_pattern(&forward, None).unwrap();
+        check_resume(automata, 5);
+    }
+}
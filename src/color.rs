@@ -0,0 +1,80 @@
+pub use colors::*;
+
+/// An RGBA color with each channel from 0.0 to 1.0.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /// Blends between evenly spaced colors as `t` goes from 0 to 1.
+    pub fn gradient(stops: &[Color], t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let index = (t as usize).min(stops.len() - 2);
+        stops[index].lerp(stops[index + 1], t - index as f32)
+    }
+
+    /// Blends linearly from this color to `other` as `t` goes from 0 to 1.
+    pub fn lerp(&self, other: Color, t: f32) -> Color {
+        Color::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+}
+
+impl From<Color> for [u8; 4] {
+    fn from(color: Color) -> [u8; 4] {
+        [
+            (color.r * 255.0) as u8,
+            (color.g * 255.0) as u8,
+            (color.b * 255.0) as u8,
+            (color.a * 255.0) as u8,
+        ]
+    }
+}
+
+#[cfg(feature = "macroquad")]
+impl From<Color> for macroquad::color::Color {
+    fn from(color: Color) -> macroquad::color::Color {
+        macroquad::color::Color::new(color.r, color.g, color.b, color.a)
+    }
+}
+
+pub mod colors {
+    use super::Color;
+
+    pub const LIGHTGRAY: Color = Color::new(0.78, 0.78, 0.78, 1.00);
+    pub const GRAY: Color = Color::new(0.51, 0.51, 0.51, 1.00);
+    pub const DARKGRAY: Color = Color::new(0.31, 0.31, 0.31, 1.00);
+    pub const YELLOW: Color = Color::new(0.99, 0.98, 0.00, 1.00);
+    pub const GOLD: Color = Color::new(1.00, 0.80, 0.00, 1.00);
+    pub const ORANGE: Color = Color::new(1.00, 0.63, 0.00, 1.00);
+    pub const PINK: Color = Color::new(1.00, 0.43, 0.76, 1.00);
+    pub const RED: Color = Color::new(0.90, 0.16, 0.22, 1.00);
+    pub const MAROON: Color = Color::new(0.75, 0.13, 0.22, 1.00);
+    pub const GREEN: Color = Color::new(0.00, 0.89, 0.19, 1.00);
+    pub const LIME: Color = Color::new(0.00, 0.62, 0.18, 1.00);
+    pub const DARKGREEN: Color = Color::new(0.00, 0.46, 0.17, 1.00);
+    pub const SKYBLUE: Color = Color::new(0.40, 0.75, 1.00, 1.00);
+    pub const BLUE: Color = Color::new(0.00, 0.47, 0.95, 1.00);
+    pub const DARKBLUE: Color = Color::new(0.00, 0.32, 0.67, 1.00);
+    pub const PURPLE: Color = Color::new(0.78, 0.48, 1.00, 1.00);
+    pub const VIOLET: Color = Color::new(0.53, 0.24, 0.75, 1.00);
+    pub const DARKPURPLE: Color = Color::new(0.44, 0.12, 0.49, 1.00);
+    pub const BEIGE: Color = Color::new(0.83, 0.69, 0.51, 1.00);
+    pub const BROWN: Color = Color::new(0.50, 0.42, 0.31, 1.00);
+    pub const DARKBROWN: Color = Color::new(0.30, 0.25, 0.18, 1.00);
+    pub const WHITE: Color = Color::new(1.00, 1.00, 1.00, 1.00);
+    pub const BLACK: Color = Color::new(0.00, 0.00, 0.00, 1.00);
+    pub const MAGENTA: Color = Color::new(1.00, 0.00, 1.00, 1.00);
+}
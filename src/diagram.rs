@@ -0,0 +1,572 @@
+use std::collections::VecDeque;
+use crate::automata::{AutomataTrait, Brush, GridSize};
+use crate::boundary::{Boundary, Neighbor};
+use crate::color::Color;
+use crate::pattern::{Pattern, PatternError, Region};
+use crate::render::Renderer;
+use crate::rng::Rng;
+use crate::snapshot::{self, Reader, SnapshotError, Writer};
+
+/// A rule for a one dimensional automata where each cell depends on itself
+/// and the cells to its left and right, numbered as Wolfram does.
+///
+/// Elementary rules have two colors and one bit of the code for each of the
+/// 8 arrangements of the three cells, e.g. rule 110. Totalistic rules have
+/// one base `colors` digit of the code for each sum of the three cells,
+/// e.g. the three color rule 777.
+#[derive(Clone, Debug)]
+pub struct WolframRule {
+    code: u64,
+    colors: u8,
+    totalistic: bool,
+    /// The next color, indexed by arrangement or sum
+    table: Box<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WolframRuleError {
+    pub message: &'static str,
+}
+
+pub struct DiagramParams {
+    pub rule: WolframRule,
+    /// The chance of each cell in the first generation having a random color,
+    /// or a single cell in the center without one
+    pub alive_ratio: Option<f32>,
+    /// The color of each cell state, starting with the empty state
+    pub palette: Vec<Color>,
+}
+
+/// A one dimensional automata drawn as a space-time diagram, with each
+/// generation a new row below the last. Once the grid is full, the oldest
+/// generations scroll off the top.
+pub struct Diagram {
+    grid_size: GridSize,
+    boundary: Boundary,
+    params: DiagramParams,
+    /// The generations on screen, from the top down
+    rows: VecDeque<Box<[u8]>>,
+    changed: usize,
+    generation: u64,
+    rng: Rng,
+    paint_state: u8,
+    renderer: Option<Box<dyn Renderer>>,
+}
+
+impl WolframRule {
+    pub fn new(code: u64, colors: u8, totalistic: bool) -> Result<WolframRule, WolframRuleError> {
+        if colors < 2 {
+            return Err(WolframRuleError {
+                message: "rules must have at least two colors",
+            });
+        }
+
+        if !totalistic && colors != 2 {
+            return Err(WolframRuleError {
+                message: "rules with more than two colors must be totalistic",
+            });
+        }
+
+        let len = match totalistic {
+            true => 3 * (colors as usize - 1) + 1,
+            false => 8,
+        };
+
+        // codes past the last digit would be ignored, so reject them
+        let max = (colors as u64).checked_pow(len as u32);
+        if max.is_some_and(|max| code >= max) {
+            return Err(WolframRuleError {
+                message: "rule code is too large for the number of colors",
+            });
+        }
+
+        let mut rest = code;
+        let table = (0..len)
+            .map(|_| {
+                let digit = (rest % colors as u64) as u8;
+                rest /= colors as u64;
+                digit
+            })
+            .collect();
+
+        Ok(WolframRule { code, colors, totalistic, table })
+    }
+
+    pub fn code(&self) -> u64 {
+        self.code
+    }
+
+    pub fn colors(&self) -> u8 {
+        self.colors
+    }
+
+    pub fn is_totalistic(&self) -> bool {
+        self.totalistic
+    }
+
+    /// The next color of a cell given its left neighbor, itself and its
+    /// right neighbor.
+    pub fn next(&self, left: u8, center: u8, right: u8) -> u8 {
+        let index = match self.totalistic {
+            true => left as usize + center as usize + right as usize,
+            false => (left as usize) << 2 | (center as usize) << 1 | right as usize,
+        };
+
+        self.table[index]
+    }
+}
+
+impl Diagram {
+    pub const NAME: &'static str = "diagram";
+
+    pub fn new(grid_size: GridSize, boundary: Boundary, seed: u64, params: DiagramParams) -> Diagram {
+        let mut diagram = Diagram {
+            grid_size,
+            boundary,
+            params,
+            rows: VecDeque::new(),
+            changed: 0,
+            generation: 0,
+            rng: Rng::new(seed),
+            paint_state: 1,
+            renderer: None,
+        };
+
+        let first_row = diagram.first_row();
+        diagram.rows.push_back(first_row);
+        diagram
+    }
+
+    /// Resumes a diagram from a snapshot, after the header has been read
+    /// with `snapshot::read_header`.
+    pub fn from_snapshot(reader: &mut Reader) -> Result<Diagram, SnapshotError> {
+        let invalid = SnapshotError {
+            message: "snapshot is truncated or invalid",
+        };
+
+        let grid_size = GridSize {
+            cols: reader.u64().ok_or(invalid)? as usize,
+            rows: reader.u64().ok_or(invalid)? as usize,
+        };
+
+        let generation = reader.u64().ok_or(invalid)?;
+        let rng = Rng::new(reader.u64().ok_or(invalid)?);
+        let boundary = reader.u8()
+            .and_then(snapshot::boundary_from_u8)
+            .ok_or(invalid)?;
+
+        let (code, colors, totalistic) = (
+            reader.u64().ok_or(invalid)?,
+            reader.u8().ok_or(invalid)?,
+            reader.u8().ok_or(invalid)? != 0,
+        );
+
+        let rule = WolframRule::new(code, colors, totalistic).map_err(|_| invalid)?;
+        let alive_ratio = match reader.u8().ok_or(invalid)? {
+            0 => None,
+            _ => Some(reader.f32().ok_or(invalid)?),
+        };
+
+        let palette_len = reader.u16().ok_or(invalid)? as usize;
+        let palette = (0..palette_len)
+            .map(|_| Some(Color::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?)))
+            .collect::<Option<Vec<Color>>>()
+            .filter(|palette| palette.len() >= colors as usize)
+            .ok_or(invalid)?;
+
+        // every row takes one byte per column, so there cannot be more of
+        // them than the rest of the snapshot holds
+        let rows_len = reader.u64().ok_or(invalid)? as usize;
+        let fits = rows_len.checked_mul(grid_size.cols)
+            .is_some_and(|len| len <= reader.len());
+
+        if grid_size.cols == 0 || rows_len == 0 || rows_len > grid_size.rows || !fits {
+            return Err(invalid);
+        }
+
+        let rows = (0..rows_len)
+            .map(|_| {
+                let row = reader.bytes(grid_size.cols)?;
+                row.iter()
+                    .all(|&color| color < colors)
+                    .then(|| row.into())
+            })
+            .collect::<Option<VecDeque<Box<[u8]>>>>()
+            .ok_or(invalid)?;
+
+        if !reader.is_empty() {
+            return Err(invalid);
+        }
+
+        Ok(Diagram {
+            grid_size,
+            boundary,
+            params: DiagramParams { rule, alive_ratio, palette },
+            rows,
+            changed: 0,
+            generation,
+            rng,
+            paint_state: 1,
+            renderer: None,
+        })
+    }
+
+    /// The first generation, either random or a single cell in the center.
+    fn first_row(&mut self) -> Box<[u8]> {
+        let cols = self.grid_size.cols;
+        let colors = self.params.rule.colors() as usize;
+        match self.params.alive_ratio {
+            Some(alive_ratio) => (0..cols)
+                .map(|_| match self.rng.gen_bool(alive_ratio) {
+                    true => 1 + self.rng.gen_below(colors - 1) as u8,
+                    false => 0,
+                })
+                .collect(),
+            None => (0..cols)
+                .map(|col| (col == cols / 2) as u8)
+                .collect(),
+        }
+    }
+
+    fn cell(&self, col: usize, row: usize) -> u8 {
+        self.rows.get(row).map_or(0, |cells| cells[col])
+    }
+
+    /// Draws every cell, including the rows not reached yet.
+    fn redraw(&mut self) {
+        let GridSize { cols, rows } = self.grid_size;
+        if let Some(renderer) = &mut self.renderer {
+            for row in 0..rows {
+                for col in 0..cols {
+                    let color = self.rows.get(row).map_or(0, |cells| cells[col]);
+                    renderer.set_pixel(col, row, self.params.palette[color as usize]);
+                }
+            }
+        }
+    }
+}
+
+impl AutomataTrait for Diagram {
+    fn next(&mut self) {
+        let cols = self.grid_size.cols;
+        // SAFETY: there is always at least one row
+        let last = self.rows.back().unwrap();
+        let at = |col: isize| match self.boundary.resolve(cols, 1, col, 0) {
+            Neighbor::Index(index) => last[index],
+            Neighbor::Fixed | Neighbor::Outside => 0,
+        };
+
+        let next: Box<[u8]> = (0..cols as isize)
+            .map(|col| self.params.rule.next(at(col - 1), at(col), at(col + 1)))
+            .collect();
+
+        self.changed = next.iter()
+            .zip(last.iter())
+            .filter(|(next, last)| next != last)
+            .count();
+
+        self.rows.push_back(next);
+        self.generation += 1;
+
+        // once the grid is full, scroll everything up a row, otherwise only
+        // the new row needs to be drawn. The last row is always kept to
+        // continue from, even if the grid has no rows to show it in.
+        if self.rows.len() > self.grid_size.rows.max(1) {
+            self.rows.pop_front();
+            self.redraw();
+        }
+        else if let Some(renderer) = &mut self.renderer {
+            let row = self.rows.len() - 1;
+            for (col, &color) in self.rows[row].iter().enumerate() {
+                renderer.set_pixel(col, row, self.params.palette[color as usize]);
+            }
+        }
+    }
+
+    fn render(&mut self) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.draw();
+        }
+    }
+
+    fn reset(&mut self) {
+        let first_row = self.first_row();
+        self.rows = VecDeque::from([first_row]);
+        self.changed = self.grid_size.cols;
+        self.generation = 0;
+        self.redraw();
+    }
+
+    /// Places the pattern at the top of the grid unless given an offset, and
+    /// continues from its last row.
+    fn load_pattern(&mut self, pattern: &Pattern, offset: Option<(usize, usize)>) -> Result<(), PatternError> {
+        let GridSize { cols, rows } = self.grid_size;
+        if pattern.width > cols || pattern.height > rows {
+            return Err(PatternError {
+                message: "pattern does not fit in the grid",
+            });
+        }
+
+        let (col_offset, row_offset) = offset.unwrap_or(((cols - pattern.width) / 2, 0));
+        if col_offset + pattern.width > cols || row_offset + pattern.height > rows {
+            return Err(PatternError {
+                message: "pattern does not fit in the grid at the given offset",
+            });
+        }
+
+        let mut history = vec![vec![0; cols].into_boxed_slice(); row_offset + pattern.height.max(1)];
+        for (col, row, state) in pattern.cells() {
+            if state >= self.params.rule.colors() {
+                return Err(PatternError {
+                    message: "pattern contains a state this cell type does not have",
+                });
+            }
+
+            history[row + row_offset][col + col_offset] = state;
+        }
+
+        self.rows = history.into();
+        self.changed = cols;
+        self.generation = 0;
+        self.redraw();
+
+        Ok(())
+    }
+
+    fn to_pattern(&self, region: Option<Region>) -> Pattern {
+        let GridSize { cols, rows } = self.grid_size;
+        let Region { col, row, width, height } = region.unwrap_or(Region {
+            col: 0,
+            row: 0,
+            width: cols,
+            height: rows,
+        });
+
+        // clip the region to the grid
+        let width = width.min(cols.saturating_sub(col));
+        let height = height.min(rows.saturating_sub(row));
+
+        let runs = (0..width)
+            .flat_map(|dcol| (0..height).map(move |drow| (dcol, drow)))
+            .filter_map(|(dcol, drow)| {
+                let state = self.cell(col + dcol, row + drow);
+                (state != 0).then_some((dcol, drow, 1, state))
+            })
+            .collect();
+
+        // only elementary rules have a notation that Golly understands
+        let rule = &self.params.rule;
+        Pattern {
+            width,
+            height,
+            rule: (!rule.is_totalistic()).then(|| format!("W{}", rule.code())),
+            runs,
+        }
+    }
+
+    fn save_snapshot(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        snapshot::write_header(&mut writer, Diagram::NAME);
+
+        writer.u64(self.grid_size.cols as u64);
+        writer.u64(self.grid_size.rows as u64);
+        writer.u64(self.generation);
+        writer.u64(self.rng.state());
+        writer.u8(snapshot::boundary_to_u8(self.boundary));
+
+        let rule = &self.params.rule;
+        writer.u64(rule.code());
+        writer.u8(rule.colors());
+        writer.u8(rule.is_totalistic() as u8);
+        match self.params.alive_ratio {
+            Some(alive_ratio) => {
+                writer.u8(1);
+                writer.f32(alive_ratio);
+            },
+            None => writer.u8(0),
+        }
+
+        writer.u16(self.params.palette.len() as u16);
+        for color in self.params.palette.iter() {
+            for channel in [color.r, color.g, color.b, color.a] {
+                writer.f32(channel);
+            }
+        }
+
+        writer.u64(self.rows.len() as u64);
+        for row in self.rows.iter() {
+            writer.bytes(row);
+        }
+
+        writer.into_bytes()
+    }
+
+    fn attach_renderer(&mut self, renderer: Box<dyn Renderer>) {
+        self.renderer = Some(renderer);
+        self.redraw();
+    }
+
+    fn grid_size(&self) -> GridSize {
+        self.grid_size
+    }
+
+    fn changed(&self) -> usize {
+        self.changed
+    }
+
+    fn topplings(&self) -> Option<usize> {
+        None
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn color(&self, col: usize, row: usize) -> Color {
+        self.params.palette[self.cell(col, row) as usize]
+    }
+
+    /// Paints a cell of a generation that has already been reached, so
+    /// painting the last row changes the generations after it.
+    fn paint(&mut self, col: usize, row: usize, brush: Brush) {
+        if col >= self.grid_size.cols || row >= self.rows.len() {
+            return;
+        }
+
+        let color = match brush {
+            Brush::Paint => self.paint_state,
+            Brush::Erase => 0,
+        };
+
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_pixel(col, row, self.params.palette[color as usize]);
+        }
+
+        self.rows[row][col] = color;
+    }
+
+    fn next_paint_state(&mut self) {
+        self.paint_state = self.paint_state % (self.params.rule.colors() - 1) + 1;
+    }
+
+    fn paint_color(&self) -> Color {
+        self.params.palette[self.paint_state as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::BLACK;
+
+    fn diagram(grid_size: GridSize, boundary: Boundary, rule: WolframRule, alive_ratio: Option<f32>) -> Diagram {
+        let palette = vec![BLACK; rule.colors() as usize];
+        Diagram::new(grid_size, boundary, 1, DiagramParams { rule, alive_ratio, palette })
+    }
+
+    #[test]
+    fn elementary_rules_have_a_bit_per_arrangement() {
+        let rule = WolframRule::new(110, 2, false).unwrap();
+        let arrangements = [(1, 1, 1), (1, 1, 0), (1, 0, 1), (1, 0, 0), (0, 1, 1), (0, 1, 0), (0, 0, 1), (0, 0, 0)];
+        let next: Vec<u8> = arrangements.iter()
+            .map(|&(left, center, right)| rule.next(left, center, right))
+            .collect();
+
+        assert_eq!(next, [0, 1, 1, 0, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn totalistic_rules_have_a_digit_per_sum() {
+        // 777 is 1001210 in base 3, read from the lowest digit for a sum of 0
+        let rule = WolframRule::new(777, 3, true).unwrap();
+        let sums = [(0, 0, 0), (1, 0, 0), (0, 1, 1), (1, 1, 1), (2, 0, 2), (2, 2, 1), (2, 2, 2)];
+        let next: Vec<u8> = sums.iter()
+            .map(|&(left, center, right)| rule.next(left, center, right))
+            .collect();
+
+        assert_eq!(next, [0, 1, 2, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn totalistic_sums_do_not_overflow_with_many_colors() {
+        let rule = WolframRule::new(7 + 5 * 200, 200, true).unwrap();
+        assert_eq!(rule.next(0, 0, 0), 7);
+        assert_eq!(rule.next(0, 1, 0), 5);
+        assert_eq!(rule.next(199, 199, 199), 0);
+
+        let rule = WolframRule::new(u64::MAX, 255, true).unwrap();
+        let mut diagram = diagram(GridSize { cols: 20, rows: 10 }, Boundary::Torus, rule, Some(1.0));
+        for _ in 0..5 {
+            diagram.next();
+        }
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        assert!(WolframRule::new(30, 1, false).is_err());
+        assert!(WolframRule::new(30, 3, false).is_err());
+        assert!(WolframRule::new(256, 2, false).is_err());
+        assert!(WolframRule::new(2186, 3, true).is_ok());
+        assert!(WolframRule::new(2187, 3, true).is_err());
+
+        // codes for rules with too many digits to check are allowed
+        assert!(WolframRule::new(u64::MAX, 255, true).is_ok());
+    }
+
+    #[test]
+    fn rule_90_draws_pascals_triangle_mod_2() {
+        let rule = WolframRule::new(90, 2, false).unwrap();
+        let mut diagram = diagram(GridSize { cols: 9, rows: 9 }, Boundary::Dead, rule, None);
+        for _ in 0..3 {
+            diagram.next();
+        }
+
+        let rows: Vec<&[u8]> = diagram.rows.iter().map(|row| &row[..]).collect();
+        assert_eq!(rows, [
+            [0, 0, 0, 0, 1, 0, 0, 0, 0],
+            [0, 0, 0, 1, 0, 1, 0, 0, 0],
+            [0, 0, 1, 0, 0, 0, 1, 0, 0],
+            [0, 1, 0, 1, 0, 1, 0, 1, 0],
+        ]);
+    }
+
+    #[test]
+    fn keeps_the_last_row_without_rows_to_show() {
+        let rule = WolframRule::new(30, 2, false).unwrap();
+        let mut diagram = diagram(GridSize { cols: 5, rows: 0 }, Boundary::Torus, rule, None);
+        for _ in 0..3 {
+            diagram.next();
+        }
+
+        assert_eq!(diagram.rows.len(), 1);
+        assert_eq!(diagram.generation(), 3);
+    }
+
+    #[test]
+    fn snapshots_resume_the_same_run() {
+        let rule = WolframRule::new(1635, 3, true).unwrap();
+        let mut diagram = diagram(GridSize { cols: 30, rows: 8 }, Boundary::Torus, rule, Some(0.5));
+        for _ in 0..5 {
+            diagram.next();
+        }
+
+        let bytes = diagram.save_snapshot();
+        let (name, mut reader) = snapshot::read_header(&bytes).unwrap();
+        assert_eq!(name, Diagram::NAME);
+        let mut resumed = Diagram::from_snapshot(&mut reader).unwrap();
+        assert_eq!(resumed.save_snapshot(), bytes);
+
+        // past the bottom of the grid, so the rows scroll
+        for _ in 0..10 {
+            diagram.next();
+            resumed.next();
+            assert_eq!(resumed.changed(), diagram.changed());
+        }
+
+        assert_eq!(resumed.save_snapshot(), diagram.save_snapshot());
+
+        // and truncated snapshots do not load
+        let (_, mut reader) = snapshot::read_header(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(Diagram::from_snapshot(&mut reader).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+use std::fs;
+use std::path::Path;
+use automata::AutomataTrait;
+use automata::pattern::Region;
+
+/// Writes the current generation, or a region of it, to a pattern file. The
+/// format is plaintext for files ending in `.cells` and RLE otherwise.
+pub fn export(automata: &dyn AutomataTrait, region: Option<Region>, path: &str) -> Result<(), String> {
+    let pattern = automata.to_pattern(region);
+    let contents = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("cells") => pattern.to_cells().map_err(|err| err.message.to_owned())?,
+        _ => pattern.to_rle(),
+    };
+
+    fs::write(path, contents).map_err(|err| err.to_string())
+}
@@ -0,0 +1,93 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::time::Instant;
+use automata::{AutomataTrait, GridSize};
+use automata::pattern::Region;
+use crate::export::export;
+
+pub struct HeadlessParams {
+    pub generations: u64,
+    pub output: String,
+    pub export: Option<String>,
+    pub region: Option<Region>,
+    pub save: Option<String>,
+}
+
+/// Runs the automata for the requested number of generations, then writes
+/// the final state as a PPM image and the number of cells that changed each
+/// generation as a CSV file, along with the number of cells that toppled for
+/// cell types that topple. The final state can also be exported as a pattern
+/// file or saved as a snapshot.
+pub fn run(mut automata: Box<dyn AutomataTrait>, params: HeadlessParams) -> io::Result<()> {
+    let HeadlessParams { generations, output, export: export_path, region, save } = params;
+
+    let mut stats = Vec::new();
+    let start = Instant::now();
+    for _ in 0..generations {
+        automata.next();
+        stats.push((automata.changed(), automata.topplings()));
+    }
+
+    let elapsed = start.elapsed();
+    println!(
+        "ran {} generations in {:.3}s ({:.1} generations per second)",
+        generations,
+        elapsed.as_secs_f64(),
+        generations as f64 / elapsed.as_secs_f64(),
+    );
+
+    let image_path = format!("{output}.ppm");
+    write_image(&*automata, &image_path)?;
+    println!("wrote final state to {image_path}");
+
+    let stats_path = format!("{output}.csv");
+    write_stats(&stats, &stats_path)?;
+    println!("wrote stats to {stats_path}");
+
+    if let Some(export_path) = export_path {
+        export(&*automata, region, &export_path).map_err(io::Error::other)?;
+        println!("exported final state to {export_path}");
+    }
+
+    if let Some(save_path) = save {
+        fs::write(&save_path, automata.save_snapshot())?;
+        println!("saved final state to {save_path}");
+    }
+
+    Ok(())
+}
+
+fn write_image(automata: &dyn AutomataTrait, path: &str) -> io::Result<()> {
+    let GridSize { cols, rows } = automata.grid_size();
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    write!(writer, "P6\n{cols} {rows}\n255\n")?;
+    for row in 0..rows {
+        for col in 0..cols {
+            let [r, g, b, _a]: [u8; 4] = automata.color(col, row).into();
+            writer.write_all(&[r, g, b])?;
+        }
+    }
+
+    writer.flush()
+}
+
+fn write_stats(stats: &[(usize, Option<usize>)], path: &str) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    // cell types either topple every generation or never do
+    let topples = stats.first().is_some_and(|(_, topplings)| topplings.is_some());
+    match topples {
+        true => writeln!(writer, "generation,changed,topplings")?,
+        false => writeln!(writer, "generation,changed")?,
+    }
+
+    for (generation, (changed, topplings)) in stats.iter().enumerate() {
+        match topplings {
+            Some(topplings) => writeln!(writer, "{},{},{}", generation + 1, changed, topplings)?,
+            None => writeln!(writer, "{},{}", generation + 1, changed)?,
+        }
+    }
+
+    writer.flush()
+}
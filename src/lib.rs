@@ -0,0 +1,16 @@
+pub mod agent;
+pub mod automata;
+pub mod boundary;
+pub mod cell;
+pub mod color;
+pub mod diagram;
+pub mod neighborhood;
+pub mod pattern;
+pub mod render;
+pub mod rng;
+pub mod snapshot;
+mod sums;
+#[cfg(feature = "macroquad")]
+pub mod texture;
+
+pub use crate::automata::{Automata, AutomataParams, AutomataTrait, Brush, GridSize};
@@ -1,25 +1,37 @@
 mod args;
-mod automata;
-mod cell;
+mod export;
+mod headless;
 mod time;
 
+use std::fs;
 use std::process::exit;
+use std::str::FromStr;
 use std::sync::LazyLock;
 use std::time::SystemTime;
 use macroquad::prelude::*;
-use macroquad::rand::srand;
 use miniquad::conf::Platform;
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap::error::ErrorKind;
 use args::*;
-use automata::{Automata, AutomataParams, AutomataTrait};
-use cell::prelude::*;
+use automata::{Automata, AutomataParams, AutomataTrait, Brush, GridSize};
+use automata::agent::Agents;
+use automata::cell::prelude::*;
+use automata::cell::life::LifeRule;
+use automata::diagram::{Diagram, DiagramParams, WolframRule};
+use automata::neighborhood::{Neighborhood, Shape};
+use automata::pattern::Region;
+use automata::rng::Rng;
+use automata::snapshot::{self, SnapshotError};
+use automata::texture::AutomataTexture;
+use export::export;
+use headless::HeadlessParams;
 use time::Ticker;
 
 static SCREEN_DIMS: LazyLock<(f32, f32)> = LazyLock::new(|| {
     (screen_width(), screen_height())
 });
 
-static SAMPLES_PER_SEC: u32 = 10;
+static FRAMES_PER_SEC: u32 = 60;
 
 fn window_conf() -> Conf {
     Conf {
@@ -33,53 +45,115 @@ fn window_conf() -> Conf {
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    let args = AutomataArgs::parse();
+fn main() {
+    let mut args = AutomataArgs::parse();
 
-    // set the screen size
-    if args.window.fullscreen {
-        set_fullscreen(true);
+    // snapshots include everything needed to build the automata, otherwise
+    // a cell type and grid size are needed
+    if args.load.is_some() && args.cell.is_some() {
+        AutomataArgs::command()
+            .error(ErrorKind::ArgumentConflict, "a cell type cannot be given when loading a snapshot, which has its own")
+            .exit();
     }
-    else {
-        let width = args.window.width.unwrap();
-        let height = args.window.height.unwrap();
-        request_new_screen_size(width, height);
+
+    if args.load.is_none() {
+        if args.cell.is_none() {
+            AutomataArgs::command()
+                .error(ErrorKind::MissingSubcommand, "a cell type is required unless loading a snapshot")
+                .exit();
+        }
+
+        if args.window.headless && (args.headless.cols.is_none() || args.headless.rows.is_none()) {
+            AutomataArgs::command()
+                .error(ErrorKind::MissingRequiredArgument, "--cols and --rows are required when running headless")
+                .exit();
+        }
+
+        // pick a random seed so that each run is different, unless one was given
+        let seed = *args.seed.get_or_insert_with(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos() as u64
+        });
+
+        println!("seed: {seed}");
     }
 
-    // This works around an issue where the true screen size is not available
-    // for the first few frames.
-    for _ in 0..3 { next_frame().await }
+    if args.window.headless {
+        let automata = match &args.load {
+            Some(path) => load_automata(&args, path),
+            None => {
+                // SAFETY: the grid size was checked above
+                let grid_size = GridSize {
+                    cols: args.headless.cols.unwrap(),
+                    rows: args.headless.rows.unwrap(),
+                };
+
+                build_automata(&args, grid_size)
+            },
+        };
+
+        let params = HeadlessParams {
+            generations: args.headless.generations,
+            output: args.headless.output,
+            export: args.headless.export,
+            region: args.headless.region,
+            save: args.headless.save,
+        };
+
+        if let Err(err) = headless::run(automata, params) {
+            eprintln!("failed to write results: {err}");
+            exit(1);
+        }
+    }
+    else {
+        macroquad::Window::from_config(window_conf(), windowed(args));
+    }
+}
 
-    // set a random seed so that each run is different
-    let time_since_epoch = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+fn build_automata(args: &AutomataArgs, grid_size: GridSize) -> Box<dyn AutomataTrait> {
+    let neighborhood = match &args.mask {
+        Some(mask) => Neighborhood::Custom(mask.clone()),
+        None => Neighborhood::Shape(args.neighborhood, args.radius as usize),
+    };
 
-    srand(time_since_epoch);
+    // non-totalistic rules depend on where each of the 8 neighbors is
+    let is_moore = neighborhood.offsets() == Neighborhood::Shape(Shape::Moore, 1).offsets();
+    let check_rule = |rule: &LifeRule| {
+        if !rule.is_totalistic() && !is_moore {
+            eprintln!("rule {rule} needs the moore neighborhood with radius 1");
+            exit(1);
+        }
+    };
 
-    // set up automata
     let params = AutomataParams {
-        cell_size: args.cell_size,
+        grid_size,
         threads: args.threads,
         chunks: args.chunks,
+        boundary: args.boundary,
+        neighborhood,
+        // SAFETY: the seed is always set in main
+        seed: args.seed.unwrap(),
     };
 
-    let mut automata: Box<dyn AutomataTrait> = match args.cell {
+    // SAFETY: the cell type was checked in main
+    let mut automata: Box<dyn AutomataTrait> = match args.cell.as_ref().unwrap() {
         CellType::Life(life_params) => {
+            let rule = pattern_rule(args).unwrap_or_else(|| life_params.rule.clone());
+            check_rule(&rule);
             Box::new(Automata::<Life>::new(
                 params,
                 LifeParams {
                     alive_ratio: life_params.percent_arg.percentage as f32 / 100.0,
-                    rule: life_params.rule,
+                    rule,
                 }
             ))
         },
         CellType::Cyclic(cyclic_params) => {
             let palette = match cyclic_params.palette {
-                Palette::Rainbow => &*COLORS,
-                Palette::Grayscale => &*GRAYSCALE,
+                Palette::Rainbow => COLORS.to_vec(),
+                Palette::Grayscale => GRAYSCALE.clone(),
             };
 
             Box::new(Automata::<Cyclic>::new(
@@ -98,35 +172,535 @@ async fn main() {
                 }
             ))
         },
+        CellType::Generations(generations_params) => {
+            let rule = pattern_rule(args).unwrap_or_else(|| generations_params.rule.clone());
+            check_rule(&rule.life);
+            Box::new(Automata::<Generations>::new(
+                params,
+                GenerationsParams {
+                    alive_ratio: generations_params.percent_arg.percentage as f32 / 100.0,
+                    rule,
+                }
+            ))
+        },
+        CellType::Ltl(ltl_params) => {
+            Box::new(Automata::<Ltl>::new(
+                params,
+                LtlParams {
+                    alive_ratio: ltl_params.percent_arg.percentage as f32 / 100.0,
+                    rule: pattern_rule(args).unwrap_or_else(|| ltl_params.rule.clone()),
+                }
+            ))
+        },
+        CellType::Wireworld => {
+            Box::new(Automata::<Wireworld>::new(params, WireworldParams))
+        },
+        CellType::Turmite(turmite_params) => {
+            let rule = turmite_params.rule.clone();
+            let cell_params = match turmite_params.palette {
+                Palette::Rainbow => TurmiteParams::from_colors(rule.colors(), COLORS),
+                Palette::Grayscale => TurmiteParams::grayscale(rule.colors()),
+            };
+
+            let mut rng = Rng::new(params.seed);
+            let agents = Agents::new(rule, turmite_params.ants as usize, grid_size, &mut rng);
+            Box::new(Automata::<Turmite>::new(params, cell_params).with_agents(agents))
+        },
+        CellType::Lenia(lenia_params) => {
+            let cell_params = LeniaParams::new(
+                lenia_params.percent_arg.percentage as f32 / 100.0,
+                lenia_params.range as usize,
+                lenia_params.peaks.clone(),
+                lenia_params.mu,
+                lenia_params.sigma,
+                lenia_params.dt,
+            );
+
+            // small ranges can fall between the rings of the kernel
+            if !cell_params.has_weight() {
+                eprintln!("--range {} and --peaks give the kernel no weight", lenia_params.range);
+                exit(1);
+            }
+
+            Box::new(Automata::<Lenia>::new(params, cell_params))
+        },
+        CellType::Smoothlife(smoothlife_params) => {
+            Box::new(Automata::<SmoothLife>::new(
+                params,
+                SmoothLifeParams::new(
+                    smoothlife_params.percentage as f32 / 100.0,
+                    smoothlife_params.range as usize,
+                    smoothlife_params.birth,
+                    smoothlife_params.survive,
+                    smoothlife_params.alpha_n,
+                    smoothlife_params.alpha_m,
+                    smoothlife_params.dt,
+                )
+            ))
+        },
+        CellType::Margolus(margolus_params) => {
+            // the shifted blocks would overlap where the grid wraps
+            if args.boundary.wraps() && (grid_size.cols % 2 == 1 || grid_size.rows % 2 == 1) {
+                eprintln!("margolus needs an even number of columns and rows to wrap around, not {}x{}", grid_size.cols, grid_size.rows);
+                exit(1);
+            }
+
+            Box::new(Automata::<Margolus>::new(
+                params,
+                MargolusParams {
+                    alive_ratio: margolus_params.percent_arg.percentage as f32 / 100.0,
+                    rule: pattern_rule(args).unwrap_or_else(|| margolus_params.rule.clone()),
+                }
+            ))
+        },
+        CellType::Sand(sand_params) => {
+            Box::new(Automata::<Sand>::new(
+                params,
+                SandParams {
+                    sand_ratio: sand_params.sand as f32 / 100.0,
+                    water_ratio: sand_params.water as f32 / 100.0,
+                }
+            ))
+        },
+        CellType::Forest(forest_params) => {
+            Box::new(Automata::<Forest>::new(
+                params,
+                ForestParams {
+                    tree_ratio: forest_params.percent_arg.percentage as f32 / 100.0,
+                    growth: forest_params.growth,
+                    lightning: forest_params.lightning,
+                }
+            ))
+        },
+        CellType::GreenbergHastings(gh_params) => {
+            // resting cells are white and excited cells are the darkest,
+            // fading back to white while refractory
+            let states = gh_params.refractory as usize + 2;
+            let palette = match gh_params.palette {
+                Palette::Rainbow => cycle(states, COLORS),
+                Palette::Grayscale => {
+                    let mut palette = fade(states);
+                    palette[1..].reverse();
+                    palette
+                },
+            };
+
+            Box::new(Automata::<GreenbergHastings>::new(
+                params,
+                GreenbergHastingsParams {
+                    threshold: gh_params.threshold,
+                    palette,
+                }
+            ))
+        },
+        CellType::Hodgepodge(hodgepodge_params) => {
+            let states = hodgepodge_params.states as usize;
+            let palette = match hodgepodge_params.palette {
+                Palette::Rainbow => blend(states, COLORS),
+                Palette::Grayscale => fade(states),
+            };
+
+            Box::new(Automata::<Hodgepodge>::new(
+                params,
+                HodgepodgeParams {
+                    k1: hodgepodge_params.k1,
+                    k2: hodgepodge_params.k2,
+                    g: hodgepodge_params.g,
+                    palette,
+                }
+            ))
+        },
+        CellType::Sandpile(sandpile_params) => {
+            // grains only leave the grid over a dead or fixed edge, so
+            // relaxing on any other boundary may never finish
+            if sandpile_params.relax && args.boundary.is_closed() {
+                eprintln!("--relax needs the dead or fixed boundary");
+                exit(1);
+            }
+
+            // heights 0 through 3, then cells about to topple
+            let palette = match sandpile_params.palette {
+                Palette::Rainbow => cycle(5, COLORS),
+                Palette::Grayscale => {
+                    let mut palette = fade(4);
+                    palette.push(automata::color::RED);
+                    palette
+                },
+            };
+
+            Box::new(Automata::<Sandpile>::new(
+                params,
+                SandpileParams {
+                    pile: sandpile_params.pile,
+                    relax: sandpile_params.relax,
+                    palette,
+                }
+            ))
+        },
+        CellType::Immigration(life_params) | CellType::Quadlife(life_params) => {
+            let colors = match args.cell {
+                Some(CellType::Immigration(_)) => 2,
+                _ => 4,
+            };
+
+            let rule = pattern_rule(args).unwrap_or_else(|| life_params.rule.clone());
+            check_rule(&rule);
+            Box::new(Automata::<ColorLife>::new(
+                params,
+                ColorLifeParams {
+                    alive_ratio: life_params.percent_arg.percentage as f32 / 100.0,
+                    rule,
+                    colors,
+                }
+            ))
+        },
+        CellType::Rps(rps_params) => {
+            let species = rps_params.species as usize;
+            let palette = match rps_params.palette {
+                Palette::Rainbow => {
+                    // the species wrap around like the rainbow does, so the
+                    // last color would be too close to the first
+                    let mut palette = blend(species + 1, COLORS);
+                    palette.pop();
+                    palette
+                },
+                Palette::Grayscale => fade(species),
+            };
+
+            Box::new(Automata::<RockPaperScissors>::new(
+                params,
+                RockPaperScissorsParams {
+                    threshold: rps_params.threshold,
+                    randomness: rps_params.randomness,
+                    palette,
+                }
+            ))
+        },
+        CellType::Elementary(elementary_params) => {
+            return build_diagram(args, grid_size, elementary_params);
+        },
+    };
+
+    if let Some(pattern) = &args.pattern {
+        if let Err(err) = automata.load_pattern(pattern, args.offset) {
+            eprintln!("failed to load pattern: {}", err.message);
+            exit(1);
+        }
+    }
+
+    automata
+}
+
+/// One dimensional rules are drawn as a space-time diagram instead of
+/// running on the grid.
+fn build_diagram(args: &AutomataArgs, grid_size: GridSize, elementary_params: &ElementaryArgs) -> Box<dyn AutomataTrait> {
+    // more than two colors only make sense for totalistic rules
+    let totalistic = elementary_params.totalistic || elementary_params.colors > 2;
+    let rule = match WolframRule::new(elementary_params.rule, elementary_params.colors, totalistic) {
+        Ok(rule) => rule,
+        Err(err) => {
+            eprintln!("invalid rule {}: {}", elementary_params.rule, err.message);
+            exit(1);
+        },
+    };
+
+    let colors = rule.colors() as usize;
+    let palette = match elementary_params.palette {
+        Palette::Rainbow => cycle(colors, COLORS),
+        Palette::Grayscale => fade(colors),
+    };
+
+    let diagram_params = DiagramParams {
+        rule,
+        alive_ratio: elementary_params.percentage.map(|percentage| percentage as f32 / 100.0),
+        palette,
+    };
+
+    // SAFETY: the seed is always set in main
+    let mut diagram = Diagram::new(grid_size, args.boundary, args.seed.unwrap(), diagram_params);
+    if let Some(pattern) = &args.pattern {
+        if let Err(err) = diagram.load_pattern(pattern, args.offset) {
+            eprintln!("failed to load pattern: {}", err.message);
+            exit(1);
+        }
+    }
+
+    Box::new(diagram)
+}
+
+/// The rule from the pattern header, when there is a pattern with a rule that
+/// is valid for the cell type. Rules that are not valid are ignored with a
+/// warning.
+fn pattern_rule<R: FromStr>(args: &AutomataArgs) -> Option<R> {
+    let rule = args.pattern.as_ref()?.rule.as_deref()?;
+    match R::from_str(rule) {
+        Ok(parsed) => {
+            println!("using rule {rule} from pattern");
+            Some(parsed)
+        },
+        Err(_) => {
+            eprintln!("warning: ignoring rule {rule} from pattern, which is not a rule for this cell type");
+            None
+        },
+    }
+}
+
+fn load_automata(args: &AutomataArgs, path: &str) -> Box<dyn AutomataTrait> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("failed to read snapshot: {err}");
+            exit(1);
+        },
+    };
+
+    let result = snapshot::read_header(&bytes).and_then(|(cell_type, mut reader)| {
+        let (threads, chunks) = (args.threads, args.chunks);
+        let automata: Box<dyn AutomataTrait> = match cell_type {
+            Life::NAME => Box::new(Automata::<Life>::from_snapshot(&mut reader, threads, chunks)?),
+            Cyclic::NAME => Box::new(Automata::<Cyclic>::from_snapshot(&mut reader, threads, chunks)?),
+            Brain::NAME => Box::new(Automata::<Brain>::from_snapshot(&mut reader, threads, chunks)?),
+            Generations::NAME => Box::new(Automata::<Generations>::from_snapshot(&mut reader, threads, chunks)?),
+            Ltl::NAME => Box::new(Automata::<Ltl>::from_snapshot(&mut reader, threads, chunks)?),
+            Wireworld::NAME => Box::new(Automata::<Wireworld>::from_snapshot(&mut reader, threads, chunks)?),
+            Turmite::NAME => Box::new(Automata::<Turmite>::from_snapshot(&mut reader, threads, chunks)?),
+            Lenia::NAME => Box::new(Automata::<Lenia>::from_snapshot(&mut reader, threads, chunks)?),
+            SmoothLife::NAME => Box::new(Automata::<SmoothLife>::from_snapshot(&mut reader, threads, chunks)?),
+            Margolus::NAME => Box::new(Automata::<Margolus>::from_snapshot(&mut reader, threads, chunks)?),
+            Sand::NAME => Box::new(Automata::<Sand>::from_snapshot(&mut reader, threads, chunks)?),
+            Forest::NAME => Box::new(Automata::<Forest>::from_snapshot(&mut reader, threads, chunks)?),
+            GreenbergHastings::NAME => Box::new(Automata::<GreenbergHastings>::from_snapshot(&mut reader, threads, chunks)?),
+            Hodgepodge::NAME => Box::new(Automata::<Hodgepodge>::from_snapshot(&mut reader, threads, chunks)?),
+            Sandpile::NAME => Box::new(Automata::<Sandpile>::from_snapshot(&mut reader, threads, chunks)?),
+            ColorLife::NAME => Box::new(Automata::<ColorLife>::from_snapshot(&mut reader, threads, chunks)?),
+            RockPaperScissors::NAME => Box::new(Automata::<RockPaperScissors>::from_snapshot(&mut reader, threads, chunks)?),
+            Diagram::NAME => Box::new(Diagram::from_snapshot(&mut reader)?),
+            _ => return Err(SnapshotError {
+                message: "snapshot has an unknown cell type",
+            }),
+        };
+
+        Ok(automata)
+    });
+
+    match result {
+        Ok(automata) => {
+            println!("resuming {path} from generation {}", automata.generation());
+            automata
+        },
+        Err(err) => {
+            eprintln!("failed to load snapshot: {}", err.message);
+            exit(1);
+        },
+    }
+}
+
+async fn windowed(args: AutomataArgs) {
+    // set the screen size
+    if args.window.fullscreen {
+        set_fullscreen(true);
+    }
+    else {
+        let width = args.window.width.unwrap();
+        let height = args.window.height.unwrap();
+        request_new_screen_size(width, height);
+    }
+
+    // This works around an issue where the true screen size is not available
+    // for the first few frames.
+    for _ in 0..3 { next_frame().await }
+
+    // determine the grid size based on screen and cell size, unless resuming
+    // from a snapshot
+    let (sw, sh) = *SCREEN_DIMS;
+    let mut automata = match &args.load {
+        Some(path) => load_automata(&args, path),
+        None => build_automata(&args, GridSize {
+            cols: (sw / args.cell_size) as usize,
+            rows: (sh / args.cell_size) as usize,
+        }),
     };
 
+    let grid_size = automata.grid_size();
+    automata.attach_renderer(Box::new(AutomataTexture::new(grid_size, *SCREEN_DIMS)));
+
     clear_background(BLACK);
 
     // enter main loop
-    let mut sample_timer = Ticker::new(SAMPLES_PER_SEC);
-    let mut render_timer = Ticker::new(args.gens_per_sec);
+    let mut frame_timer = Ticker::new(FRAMES_PER_SEC);
+    let mut gen_timer = Ticker::new(args.gens_per_sec);
+    let mut paused = false;
+    let mut last_painted: Option<(isize, isize)> = None;
+    let mut selection_anchor: Option<(isize, isize)> = None;
+    let mut selection: Option<Region> = None;
     loop {
-        if sample_timer.tick() {
+        if !paused && gen_timer.tick() {
+            // calculate next generation of automata
+            automata.next();
+        }
+
+        if frame_timer.tick() {
             // handle key presses
             if is_key_down(KeyCode::Q) {
                 exit(0);
             }
-            
+
+            if is_key_pressed(KeyCode::Space) {
+                paused = !paused;
+            }
+
+            if paused && (is_key_pressed(KeyCode::N) || is_key_pressed(KeyCode::Right)) {
+                automata.next();
+            }
+
+            if is_key_pressed(KeyCode::R) {
+                automata.reset();
+            }
+
+            if is_key_pressed(KeyCode::Tab) {
+                automata.next_paint_state();
+            }
+
+            // handle selecting a region while holding shift
+            let hovered = screen_to_grid(grid_size, mouse_position());
+            let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            if shift && is_mouse_button_pressed(MouseButton::Left) {
+                selection_anchor = Some(hovered);
+            }
+
+            if let Some(anchor) = selection_anchor {
+                if is_mouse_button_down(MouseButton::Left) {
+                    selection = Some(region_between(grid_size, anchor, hovered));
+                }
+                else {
+                    selection_anchor = None;
+                }
+            }
+
+            if is_key_pressed(KeyCode::Escape) {
+                selection = None;
+            }
+
+            if is_key_pressed(KeyCode::S) {
+                let path = format!("automata-{}.snap", automata.generation());
+                match fs::write(&path, automata.save_snapshot()) {
+                    Ok(()) => println!("saved generation {} to {path}", automata.generation()),
+                    Err(err) => eprintln!("failed to save {path}: {err}"),
+                }
+            }
+
+            // export the selection, or the whole grid without one
+            for (key, extension) in [(KeyCode::E, "rle"), (KeyCode::C, "cells")] {
+                if is_key_pressed(key) {
+                    let path = format!("automata-{}.{extension}", automata.generation());
+                    match export(&*automata, selection, &path) {
+                        Ok(()) => println!("exported generation {} to {path}", automata.generation()),
+                        Err(err) => eprintln!("failed to export {path}: {err}"),
+                    }
+                }
+            }
+
+            // handle painting, filling in cells between frames while dragging
+            let brush = if selection_anchor.is_some() {
+                None
+            }
+            else if is_mouse_button_down(MouseButton::Left) {
+                Some(Brush::Paint)
+            }
+            else if is_mouse_button_down(MouseButton::Right) {
+                Some(Brush::Erase)
+            }
+            else {
+                None
+            };
+
+            match brush {
+                Some(brush) => {
+                    let from = last_painted.unwrap_or(hovered);
+                    for (col, row) in grid_line(from, hovered) {
+                        if col >= 0 && row >= 0 {
+                            automata.paint(col as usize, row as usize, brush);
+                        }
+                    }
+
+                    last_painted = Some(hovered);
+                },
+                None => last_painted = None,
+            }
+
             let (_wheel_x, wheel_y) = mouse_wheel();
             match wheel_y {
-                -1.0 => render_timer.dec_rate(),
-                 1.0 => render_timer.inc_rate(),
+                -1.0 => gen_timer.dec_rate(),
+                 1.0 => gen_timer.inc_rate(),
                 _ => {}
             }
-        }
-
-        if render_timer.tick() {
-            // calculate next generation of automata
-            automata.next();
 
-            // render automata on screen
+            // render automata on screen, outlining the hovered cell with
+            // the paint color
             automata.render();
+
+            let (cell_width, cell_height) = (sw / grid_size.cols as f32, sh / grid_size.rows as f32);
+            draw_rectangle_lines(
+                hovered.0 as f32 * cell_width,
+                hovered.1 as f32 * cell_height,
+                cell_width,
+                cell_height,
+                2.0,
+                automata.paint_color().into(),
+            );
+
+            if let Some(Region { col, row, width, height }) = selection {
+                draw_rectangle_lines(
+                    col as f32 * cell_width,
+                    row as f32 * cell_height,
+                    width as f32 * cell_width,
+                    height as f32 * cell_height,
+                    2.0,
+                    GOLD,
+                );
+            }
+
+            // show the size of the last avalanche for cells that topple
+            if let Some(topplings) = automata.topplings() {
+                let label = format!("avalanche: {topplings} topplings");
+                let size = measure_text(&label, None, 24, 1.0);
+                draw_rectangle(0.0, 0.0, size.width + 16.0, size.height + 16.0, BLACK);
+                draw_text(&label, 8.0, 8.0 + size.offset_y, 24.0, WHITE);
+            }
+
             next_frame().await;
         }
     }
 }
+
+fn screen_to_grid(grid_size: GridSize, (x, y): (f32, f32)) -> (isize, isize) {
+    let (sw, sh) = *SCREEN_DIMS;
+    let col = (x / sw * grid_size.cols as f32).floor() as isize;
+    let row = (y / sh * grid_size.rows as f32).floor() as isize;
+    (col, row)
+}
+
+fn region_between(grid_size: GridSize, from: (isize, isize), to: (isize, isize)) -> Region {
+    let clamp_col = |col: isize| col.clamp(0, grid_size.cols as isize - 1) as usize;
+    let clamp_row = |row: isize| row.clamp(0, grid_size.rows as isize - 1) as usize;
+    let (from_col, to_col) = (clamp_col(from.0), clamp_col(to.0));
+    let (from_row, to_row) = (clamp_row(from.1), clamp_row(to.1));
+
+    Region {
+        col: from_col.min(to_col),
+        row: from_row.min(to_row),
+        width: from_col.abs_diff(to_col) + 1,
+        height: from_row.abs_diff(to_row) + 1,
+    }
+}
+
+fn grid_line(from: (isize, isize), to: (isize, isize)) -> impl Iterator<Item = (isize, isize)> {
+    let (dcol, drow) = (to.0 - from.0, to.1 - from.1);
+    let steps = dcol.abs().max(drow.abs()).max(1);
+    (0..=steps).map(move |step| {
+        let t = step as f32 / steps as f32;
+        let col = from.0 + (dcol as f32 * t).round() as isize;
+        let row = from.1 + (drow as f32 * t).round() as isize;
+        (col, row)
+    })
+}
@@ -0,0 +1,150 @@
+use std::str::FromStr;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Shape {
+    /// Every cell within a square of the given radius
+    #[default]
+    Moore,
+    /// Every cell within a diamond of the given radius
+    VonNeumann,
+    /// Every cell within a hexagon of the given radius, on a skewed grid
+    Hexagonal,
+}
+
+#[derive(Clone, Debug)]
+pub enum Neighborhood {
+    Shape(Shape, usize),
+    Custom(Mask),
+}
+
+#[derive(Clone, Debug)]
+pub struct Mask {
+    offsets: Box<[(isize, isize)]>,
+}
+
+#[derive(Debug)]
+pub struct MaskParseError {
+    pub message: &'static str,
+}
+
+impl Neighborhood {
+    /// Returns the (column, row) offsets of each neighbor, ordered row by row
+    /// from the top left.
+    pub fn offsets(&self) -> Box<[(isize, isize)]> {
+        let (shape, radius) = match self {
+            Neighborhood::Shape(shape, radius) => (*shape, *radius as isize),
+            Neighborhood::Custom(mask) => return mask.offsets.clone(),
+        };
+
+        (-radius..=radius)
+            .flat_map(|drow| (-radius..=radius).map(move |dcol| (dcol, drow)))
+            .filter(|&(dcol, drow)| (dcol, drow) != (0, 0))
+            .filter(|&(dcol, drow)| match shape {
+                Shape::Moore => true,
+                Shape::VonNeumann => dcol.abs() + drow.abs() <= radius,
+                Shape::Hexagonal => (dcol - drow).abs() <= radius,
+            })
+            .collect()
+    }
+}
+
+impl Mask {
+    /// Creates a mask from (column, row) offsets, which should not include
+    /// the center.
+    pub fn from_offsets(offsets: Box<[(isize, isize)]>) -> Mask {
+        Mask { offsets }
+    }
+}
+
+impl FromStr for Mask {
+    type Err = MaskParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.split('/').collect();
+        let height = lines.len();
+        let width = lines[0].len();
+
+        if height.is_multiple_of(2) || width.is_multiple_of(2) {
+            return Err(MaskParseError {
+                message: "mask must have an odd number of rows and columns",
+            });
+        }
+
+        if lines.iter().any(|line| line.len() != width) {
+            return Err(MaskParseError {
+                message: "every row of the mask must be the same length",
+            });
+        }
+
+        let (rcol, rrow) = ((width / 2) as isize, (height / 2) as isize);
+        let mut offsets = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            for (col, char) in line.chars().enumerate() {
+                let offset = (col as isize - rcol, row as isize - rrow);
+                match char {
+                    '0' => {},
+                    '1' if offset == (0, 0) => return Err(MaskParseError {
+                        message: "mask cannot include the center cell",
+                    }),
+                    '1' => offsets.push(offset),
+                    _ => return Err(MaskParseError {
+                        message: "mask can only include '0', '1' and '/'",
+                    }),
+                }
+            }
+        }
+
+        Ok(Mask {
+            offsets: offsets.into_boxed_slice(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(shape: Shape, radius: usize) -> Box<[(isize, isize)]> {
+        Neighborhood::Shape(shape, radius).offsets()
+    }
+
+    #[test]
+    fn shapes_have_the_expected_neighbors() {
+        assert_eq!(&*offsets(Shape::Moore, 1), [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)]);
+        assert_eq!(&*offsets(Shape::VonNeumann, 1), [(0, -1), (-1, 0), (1, 0), (0, 1)]);
+        assert_eq!(&*offsets(Shape::Hexagonal, 1), [(-1, -1), (0, -1), (-1, 0), (1, 0), (0, 1), (1, 1)]);
+
+        assert_eq!(offsets(Shape::Moore, 2).len(), 24);
+        assert_eq!(offsets(Shape::VonNeumann, 2).len(), 12);
+        assert_eq!(offsets(Shape::Hexagonal, 2).len(), 18);
+    }
+
+    #[test]
+    fn masks_match_shapes() {
+        let mask = |s: &str| Neighborhood::Custom(s.parse().unwrap()).offsets();
+        assert_eq!(mask("010/101/010"), offsets(Shape::VonNeumann, 1));
+        assert_eq!(mask("111/101/111"), offsets(Shape::Moore, 1));
+        assert_eq!(mask("00100/01110/11011/01110/00100"), offsets(Shape::VonNeumann, 2));
+
+        // masks can be wider than they are tall
+        assert_eq!(&*mask("10001"), [(-2, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn rejects_bad_masks() {
+        let bad = [
+            ("", "mask must have an odd number of rows and columns"),
+            ("11/11", "mask must have an odd number of rows and columns"),
+            ("010/101", "mask must have an odd number of rows and columns"),
+            ("010/10101/010", "every row of the mask must be the same length"),
+            ("010/111/010", "mask cannot include the center cell"),
+            ("010/1x1/010", "mask can only include '0', '1' and '/'"),
+        ];
+
+        for (s, message) in bad {
+            assert_eq!(s.parse::<Mask>().err().map(|err| err.message), Some(message), "{s:?}");
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use std::str::FromStr;
+
+/// A pattern of cell states, as read from a Golly-style RLE file.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub rule: Option<String>,
+    /// The (column, row, length, state) of every run of cells along a row
+    /// with the same non-zero state
+    pub runs: Vec<(usize, usize, usize, u8)>,
+}
+
+/// A rectangle of the grid, in cells.
+#[derive(Clone, Copy, Debug)]
+pub struct Region {
+    pub col: usize,
+    pub row: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PatternError {
+    pub message: &'static str,
+}
+
+impl Pattern {
+    /// The (column, row, state) of every cell with a non-zero state.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, u8)> + '_ {
+        self.runs.iter()
+            .flat_map(|&(col, row, length, state)| (col..col + length).map(move |col| (col, row, state)))
+    }
+
+    /// Writes the pattern in Golly-style RLE, with lines of at most 70
+    /// characters.
+    pub fn to_rle(&self) -> String {
+        let mut rle = format!("x = {}, y = {}", self.width, self.height);
+        if let Some(rule) = &self.rule {
+            rle.push_str(&format!(", rule = {rule}"));
+        }
+
+        rle.push('\n');
+
+        // two state patterns use 'b' and 'o', otherwise use '.' and letters
+        let multistate = self.runs.iter().any(|&(_, _, _, state)| state > 1);
+        let tag = |state: u8| -> String {
+            match (multistate, state) {
+                (false, 0) => "b".to_owned(),
+                (false, _) => "o".to_owned(),
+                (true, 0) => ".".to_owned(),
+                (true, state) => {
+                    let (prefix, letter) = ((state - 1) / 24, (state - 1) % 24);
+                    let letter = char::from(b'A' + letter);
+                    match prefix {
+                        0 => letter.to_string(),
+                        _ => format!("{}{letter}", char::from(b'p' + prefix - 1)),
+                    }
+                },
+            }
+        };
+
+        // collect runs of (count, tag), skipping dead cells at the end of rows
+        let grid = self.to_grid();
+        let mut runs: Vec<(usize, String)> = Vec::new();
+        let mut push_tag = |tag: String| {
+            match runs.last_mut() {
+                Some((count, last_tag)) if *last_tag == tag => *count += 1,
+                _ => runs.push((1, tag)),
+            }
+        };
+
+        for row in grid.iter() {
+            let end = row.iter().rposition(|&state| state != 0).map_or(0, |pos| pos + 1);
+            for &state in row[..end].iter() {
+                push_tag(tag(state));
+            }
+
+            push_tag("$".to_owned());
+        }
+
+        // the last row ends the pattern instead
+        while runs.last().is_some_and(|(_, tag)| tag == "$") {
+            runs.pop();
+        }
+
+        let mut line = String::new();
+        let items = runs.into_iter()
+            .map(|(count, tag)| match count {
+                1 => tag,
+                _ => format!("{count}{tag}"),
+            })
+            .chain(std::iter::once("!".to_owned()));
+
+        for item in items {
+            if line.len() + item.len() > 70 {
+                rle.push_str(&line);
+                rle.push('\n');
+                line.clear();
+            }
+
+            line.push_str(&item);
+        }
+
+        rle.push_str(&line);
+        rle.push('\n');
+        rle
+    }
+
+    /// Writes the pattern in plaintext, which only supports two states.
+    pub fn to_cells(&self) -> Result<String, PatternError> {
+        if self.runs.iter().any(|&(_, _, _, state)| state > 1) {
+            return Err(PatternError {
+                message: "plaintext patterns can only have two states",
+            });
+        }
+
+        let mut cells = String::new();
+        for row in self.to_grid() {
+            let end = row.iter().rposition(|&state| state != 0).map_or(0, |pos| pos + 1);
+            cells.extend(row[..end].iter().map(|&state| if state == 0 { '.' } else { 'O' }));
+            cells.push('\n');
+        }
+
+        Ok(cells)
+    }
+
+    /// Reads a plaintext pattern, with one line per row and one character per
+    /// cell. Lines starting with '!' are comments.
+    ///
+    /// Both '.cells' files ('.' for dead and 'O' or '*' for alive) and the
+    /// common text format for Wireworld circuits (' ' or '.' for empty, '@'
+    /// for heads, '~' for tails and '#' for conductors) are supported.
+    pub fn from_plaintext(s: &str) -> Result<Pattern, PatternError> {
+        let lines: Vec<&str> = s.lines()
+            .filter(|line| !line.starts_with('!'))
+            .map(str::trim_end)
+            .collect();
+
+        let mut runs = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            for (col, char) in line.chars().enumerate() {
+                let state = match char {
+                    '.' | ' ' => 0,
+                    'O' | '*' | '@' => 1,
+                    '~' => 2,
+                    '#' => 3,
+                    _ => return Err(PatternError {
+                        message: "pattern contains an unknown cell state",
+                    }),
+                };
+
+                if state != 0 {
+                    runs.push((col, row, 1, state));
+                }
+            }
+        }
+
+        // trailing empty lines are not part of the pattern
+        let height = lines.iter().rposition(|line| !line.is_empty()).map_or(0, |row| row + 1);
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        Ok(Pattern {
+            width,
+            height,
+            rule: None,
+            runs,
+        })
+    }
+
+    fn to_grid(&self) -> Vec<Vec<u8>> {
+        let mut grid = vec![vec![0; self.width]; self.height];
+        for (col, row, state) in self.cells() {
+            grid[row][col] = state;
+        }
+
+        grid
+    }
+
+    fn parse_header(line: &str) -> Result<(usize, usize, Option<String>), PatternError> {
+        let (mut width, mut height, mut rule) = (None, None, None);
+
+        // the rule comes last and may include commas, e.g. "R5,C0,M1,S34..58,B34..45,NM"
+        let line = match line.split_once("rule") {
+            Some((fields, rule_field)) => {
+                let value = rule_field.trim_start().strip_prefix('=').ok_or(PatternError {
+                    message: "pattern header fields must be of the form 'key = value'",
+                })?;
+
+                rule = Some(value.trim().to_owned());
+                fields.trim_end().trim_end_matches(',')
+            },
+            None => line,
+        };
+
+        for field in line.split(',') {
+            let (key, value) = field.split_once('=').ok_or(PatternError {
+                message: "pattern header fields must be of the form 'key = value'",
+            })?;
+
+            let value = value.trim();
+            match key.trim() {
+                "x" => width = value.parse().ok(),
+                "y" => height = value.parse().ok(),
+                _ => {},
+            }
+        }
+
+        match (width, height) {
+            (Some(width), Some(height)) => Ok((width, height, rule)),
+            _ => Err(PatternError {
+                message: "pattern header must include a valid 'x' and 'y'",
+            }),
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        let header = lines.next().ok_or(PatternError {
+            message: "pattern is empty",
+        })?;
+
+        let (width, height, rule) = Pattern::parse_header(header)?;
+
+        let too_large = PatternError {
+            message: "pattern is larger than the size in its header",
+        };
+
+        let mut runs = Vec::new();
+        let (mut col, mut row): (usize, usize) = (0, 0);
+        let mut count: Option<usize> = None;
+        let mut prefix: Option<u32> = None;
+        'body: for char in lines.flat_map(str::chars) {
+            let state = match char {
+                '0'..='9' => {
+                    let digit = char.to_digit(10).unwrap() as usize;
+                    count = count.unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|count| count.checked_add(digit));
+
+                    if count.is_none() {
+                        return Err(PatternError {
+                            message: "pattern contains a run count that is too large",
+                        });
+                    }
+
+                    continue;
+                },
+                'p'..='y' => {
+                    prefix = Some(char as u32 - 'p' as u32 + 1);
+                    continue;
+                },
+                '$' => {
+                    row = row.checked_add(count.take().unwrap_or(1)).ok_or(too_large)?;
+                    col = 0;
+                    continue;
+                },
+                '!' => break 'body,
+                'b' | '.' => 0,
+                'o' => 1,
+                'A'..='X' => {
+                    let state = prefix.take().unwrap_or(0) * 24 + (char as u32 - 'A' as u32 + 1);
+                    u8::try_from(state).map_err(|_| PatternError {
+                        message: "pattern contains a state above 255",
+                    })?
+                },
+                _ if char.is_whitespace() => continue,
+                _ => return Err(PatternError {
+                    message: "pattern contains an unknown cell state",
+                }),
+            };
+
+            // check each run against the header before storing it, so a
+            // huge run cannot use up memory
+            let run = count.take().unwrap_or(1);
+            let end = col.checked_add(run)
+                .filter(|&end| end <= width)
+                .ok_or(too_large)?;
+
+            if state != 0 && run > 0 {
+                if row >= height {
+                    return Err(too_large);
+                }
+
+                runs.push((col, row, run, state));
+            }
+
+            col = end;
+        }
+
+        Ok(Pattern {
+            width,
+            height,
+            rule,
+            runs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+
+    fn cells(pattern: &Pattern) -> Vec<(usize, usize, u8)> {
+        let mut cells: Vec<_> = pattern.cells().collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn glider_round_trips_through_rle() {
+        let glider = Pattern::from_str(GLIDER).unwrap();
+        assert_eq!((glider.width, glider.height), (3, 3));
+        assert_eq!(glider.rule.as_deref(), Some("B3/S23"));
+        assert_eq!(cells(&glider), [(0, 2, 1), (1, 0, 1), (1, 2, 1), (2, 1, 1), (2, 2, 1)]);
+
+        let rle = glider.to_rle();
+        assert_eq!(rle, GLIDER);
+
+        let again = Pattern::from_str(&rle).unwrap();
+        assert_eq!((again.width, again.height, again.rule.as_deref()), (3, 3, Some("B3/S23")));
+        assert_eq!(cells(&again), cells(&glider));
+    }
+
+    #[test]
+    fn glider_round_trips_through_plaintext() {
+        let glider = Pattern::from_str(GLIDER).unwrap();
+        let text = glider.to_cells().unwrap();
+        assert_eq!(text, ".O\n..O\nOOO\n");
+
+        let again = Pattern::from_plaintext(&text).unwrap();
+        assert_eq!((again.width, again.height), (3, 3));
+        assert_eq!(cells(&again), cells(&glider));
+    }
+
+    #[test]
+    fn reads_multistate_letters() {
+        let pattern = Pattern::from_str("x = 4, y = 1\nA.pAyO!").unwrap();
+        assert_eq!(cells(&pattern), [(0, 0, 1), (2, 0, 25), (3, 0, 255)]);
+
+        // and writes them back the same way
+        assert!(pattern.to_rle().ends_with("\nA.pAyO!\n"));
+    }
+
+    #[test]
+    fn keeps_runs_as_runs() {
+        let pattern = Pattern::from_str("x = 1000000, y = 2\n1000000o$999999b2A!").unwrap_err();
+        assert_eq!(pattern.message, "pattern is larger than the size in its header");
+
+        let pattern = Pattern::from_str("x = 1000000, y = 2\n1000000o$999999bA!").unwrap();
+        assert_eq!(pattern.runs, [(0, 0, 1000000, 1), (999999, 1, 1, 1)]);
+    }
+
+    #[test]
+    fn reads_rules_with_commas() {
+        let pattern = Pattern::from_str("x = 2, y = 1, rule = R5,C0,M1,S34..58,B34..45,NM\n2o!").unwrap();
+        assert_eq!(pattern.rule.as_deref(), Some("R5,C0,M1,S34..58,B34..45,NM"));
+        assert_eq!((pattern.width, pattern.height), (2, 1));
+        assert_eq!(cells(&pattern), [(0, 0, 1), (1, 0, 1)]);
+    }
+
+    #[test]
+    fn skips_comments_and_whitespace() {
+        let pattern = Pattern::from_str("#N Blinker\n#C a comment\nx = 3, y = 1\n 3o\n!").unwrap();
+        assert_eq!(cells(&pattern), [(0, 0, 1), (1, 0, 1), (2, 0, 1)]);
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        let malformed = [
+            "",
+            "#C only comments",
+            "bo$2bo$3o!",
+            "x = 3\nbo!",
+            "x = three, y = 3\nbo!",
+            "x = 3, y = 3, rule B3/S23\nbo!",
+            "x = 3, y = 3\nbz!",
+            "x = 3, y = 3\n4o!",
+            "x = 3, y = 3\n3$o!",
+            "x = 3, y = 1\nyX!",
+            "x = 3, y = 1\n99999999999999999999999o!",
+            "x = 3, y = 1\n1000000000o!",
+            "x = 3, y = 1\n99999999999999999999$o!",
+        ];
+
+        for rle in malformed {
+            assert!(Pattern::from_str(rle).is_err(), "{rle:?} should not parse");
+        }
+
+        assert!(Pattern::from_plaintext("..O\n.x.").is_err());
+    }
+}
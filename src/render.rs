@@ -0,0 +1,7 @@
+use crate::color::Color;
+
+/// Something that cells can be drawn onto, one pixel per cell.
+pub trait Renderer {
+    fn set_pixel(&mut self, col: usize, row: usize, color: Color);
+    fn draw(&mut self);
+}
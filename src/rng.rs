@@ -0,0 +1,48 @@
+/// A small, seedable random number generator (SplitMix64) so that runs can
+/// be reproduced from their seed.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            state: seed,
+        }
+    }
+
+    /// A generator for one cell in a generation, from a seed drawn once per
+    /// generation and the index of the cell.
+    pub fn for_cell(seed: u64, index: usize) -> Rng {
+        Rng::new(seed.wrapping_add((index as u64).wrapping_mul(0xD1B5_4A32_D192_ED03)))
+    }
+
+    /// The current state, which `Rng::new` resumes from.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a float in the range [0, 1).
+    pub fn gen_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns an integer in the range [0, bound).
+    pub fn gen_below(&mut self, bound: usize) -> usize {
+        (((self.next_u64() >> 32) * bound as u64) >> 32) as usize
+    }
+
+    /// Returns true with the given probability.
+    pub fn gen_bool(&mut self, probability: f32) -> bool {
+        self.gen_f32() < probability
+    }
+}
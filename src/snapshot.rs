@@ -0,0 +1,220 @@
+use crate::boundary::Boundary;
+
+/// Every snapshot starts with these bytes, followed by the version.
+pub const MAGIC: &[u8; 8] = b"AUTOMATA";
+pub const VERSION: u16 = 3;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotError {
+    pub message: &'static str,
+}
+
+/// Writes little endian values to a snapshot.
+#[derive(Default)]
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+/// Reads little endian values from a snapshot, returning `None` once the
+/// snapshot runs out of bytes.
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Writer {
+    pub fn new() -> Writer {
+        Writer::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn str(&mut self, value: &str) {
+        self.u16(value.len() as u16);
+        self.bytes(value.as_bytes());
+    }
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes }
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(bytes)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.bytes(2)?.try_into().ok()?))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    pub fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    pub fn str(&mut self) -> Option<&'a str> {
+        let len = self.u16()? as usize;
+        std::str::from_utf8(self.bytes(len)?).ok()
+    }
+
+    /// The number of bytes left to read.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// Reads the start of a snapshot, returning the name of the cell type it
+/// holds and a reader positioned at the rest of the snapshot.
+pub fn read_header(bytes: &[u8]) -> Result<(&str, Reader<'_>), SnapshotError> {
+    let mut reader = Reader::new(bytes);
+    if reader.bytes(MAGIC.len()) != Some(MAGIC) {
+        return Err(SnapshotError {
+            message: "file is not a snapshot",
+        });
+    }
+
+    match reader.u16() {
+        Some(VERSION) => {},
+        _ => return Err(SnapshotError {
+            message: "snapshot version is not supported",
+        }),
+    }
+
+    let cell_type = reader.str().ok_or(SnapshotError {
+        message: "snapshot is missing its cell type",
+    })?;
+
+    Ok((cell_type, reader))
+}
+
+pub fn write_header(writer: &mut Writer, cell_type: &str) {
+    writer.bytes(MAGIC);
+    writer.u16(VERSION);
+    writer.str(cell_type);
+}
+
+pub(crate) fn boundary_to_u8(boundary: Boundary) -> u8 {
+    match boundary {
+        Boundary::Dead => 0,
+        Boundary::Torus => 1,
+        Boundary::Klein => 2,
+        Boundary::Mirror => 3,
+        Boundary::Fixed => 4,
+    }
+}
+
+pub(crate) fn boundary_from_u8(value: u8) -> Option<Boundary> {
+    match value {
+        0 => Some(Boundary::Dead),
+        1 => Some(Boundary::Torus),
+        2 => Some(Boundary::Klein),
+        3 => Some(Boundary::Mirror),
+        4 => Some(Boundary::Fixed),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_what_was_written() {
+        let mut writer = Writer::new();
+        write_header(&mut writer, "life");
+        writer.u8(7);
+        writer.u16(300);
+        writer.u64(u64::MAX - 1);
+        writer.i64(-5);
+        writer.f32(0.25);
+        writer.str("B3/S23");
+        let bytes = writer.into_bytes();
+
+        let (cell_type, mut reader) = read_header(&bytes).unwrap();
+        assert_eq!(cell_type, "life");
+        assert_eq!(reader.u8(), Some(7));
+        assert_eq!(reader.u16(), Some(300));
+        assert_eq!(reader.u64(), Some(u64::MAX - 1));
+        assert_eq!(reader.i64(), Some(-5));
+        assert_eq!(reader.f32(), Some(0.25));
+        assert_eq!(reader.len(), 8);
+        assert_eq!(reader.str(), Some("B3/S23"));
+        assert!(reader.is_empty());
+        assert_eq!(reader.u8(), None);
+    }
+
+    #[test]
+    fn rejects_other_files_and_versions() {
+        assert!(read_header(b"").is_err());
+        assert!(read_header(b"P6\n3 3\n255\n").is_err());
+
+        let mut writer = Writer::new();
+        writer.bytes(MAGIC);
+        writer.u16(VERSION + 1);
+        writer.str("life");
+        assert!(read_header(&writer.into_bytes()).is_err());
+
+        // a cell type longer than the rest of the snapshot
+        let mut writer = Writer::new();
+        writer.bytes(MAGIC);
+        writer.u16(VERSION);
+        writer.u16(100);
+        assert!(read_header(&writer.into_bytes()).is_err());
+    }
+
+    #[test]
+    fn boundaries_round_trip() {
+        for boundary in [Boundary::Dead, Boundary::Torus, Boundary::Klein, Boundary::Mirror, Boundary::Fixed] {
+            assert_eq!(boundary_from_u8(boundary_to_u8(boundary)), Some(boundary));
+        }
+
+        assert_eq!(boundary_from_u8(5), None);
+    }
+}
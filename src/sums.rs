@@ -0,0 +1,99 @@
+use crate::automata::GridSize;
+use crate::boundary::{Boundary, Neighbor};
+
+/// Sums the weight of every cell's neighbors with a summed-area table, so
+/// wide neighborhoods take a few lookups per cell instead of one per
+/// neighbor.
+pub(crate) struct NeighborSums {
+    /// The neighborhood as rectangles of (column, row) offsets, each given as
+    /// (min column, max column, min row, max row)
+    rects: Box<[(isize, isize, isize, isize)]>,
+    /// How far the neighborhood reaches past the edge of the grid
+    pad: usize,
+    /// The number of rows in the table, one more than the padded grid
+    table_rows: usize,
+    /// The sum of every padded cell above and to the left, column-major
+    table: Box<[u32]>,
+}
+
+impl NeighborSums {
+    pub fn new(grid_size: GridSize, offsets: &[(isize, isize)]) -> NeighborSums {
+        let pad = offsets.iter()
+            .map(|&(dcol, drow)| dcol.unsigned_abs().max(drow.unsigned_abs()))
+            .max()
+            .unwrap_or(0);
+
+        // split each column of the neighborhood into runs of rows
+        let mut sorted = offsets.to_vec();
+        sorted.sort();
+        let mut runs: Vec<(isize, isize, isize)> = Vec::new();
+        for (dcol, drow) in sorted {
+            match runs.last_mut() {
+                Some((col, _, max_row)) if *col == dcol && *max_row + 1 == drow => *max_row = drow,
+                _ => runs.push((dcol, drow, drow)),
+            }
+        }
+
+        // then join neighboring columns with the same runs into rectangles
+        let mut rects: Vec<(isize, isize, isize, isize)> = Vec::new();
+        for (dcol, min_row, max_row) in runs {
+            let joined = rects.iter_mut().find(|rect| {
+                rect.1 + 1 == dcol && (rect.2, rect.3) == (min_row, max_row)
+            });
+
+            match joined {
+                Some(rect) => rect.1 = dcol,
+                None => rects.push((dcol, dcol, min_row, max_row)),
+            }
+        }
+
+        let table_cols = grid_size.cols + 2 * pad + 1;
+        let table_rows = grid_size.rows + 2 * pad + 1;
+        NeighborSums {
+            rects: rects.into_boxed_slice(),
+            pad,
+            table_rows,
+            table: vec![0; table_cols * table_rows].into_boxed_slice(),
+        }
+    }
+
+    /// Rebuilds the table from the weights of the current generation.
+    pub fn update<C: Default>(&mut self, grid_size: GridSize, boundary: Boundary, cells: &[C], weight: fn(&C) -> u32) {
+        let GridSize { cols, rows } = grid_size;
+        let pad = self.pad as isize;
+        let fixed = weight(&C::default());
+
+        let table_cols = self.table.len() / self.table_rows;
+        for tcol in 1..table_cols {
+            let mut column_sum = 0;
+            for trow in 1..self.table_rows {
+                let col = tcol as isize - 1 - pad;
+                let row = trow as isize - 1 - pad;
+                column_sum += match boundary.resolve(cols, rows, col, row) {
+                    Neighbor::Index(index) => weight(&cells[index]),
+                    Neighbor::Fixed => fixed,
+                    Neighbor::Outside => 0,
+                };
+
+                let index = tcol * self.table_rows + trow;
+                self.table[index] = self.table[index - self.table_rows] + column_sum;
+            }
+        }
+    }
+
+    /// The total weight of the neighbors of a cell.
+    pub fn sum(&self, col: usize, row: usize) -> u32 {
+        let at = |tcol: isize, trow: isize| {
+            self.table[tcol as usize * self.table_rows + trow as usize]
+        };
+
+        let (col, row) = ((col + self.pad) as isize, (row + self.pad) as isize);
+        self.rects.iter()
+            .map(|&(min_col, max_col, min_row, max_row)| {
+                let (left, right) = (col + min_col, col + max_col + 1);
+                let (top, bottom) = (row + min_row, row + max_row + 1);
+                at(right, bottom) + at(left, top) - at(left, bottom) - at(right, top)
+            })
+            .sum()
+    }
+}
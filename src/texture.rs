@@ -0,0 +1,45 @@
+use macroquad::prelude::*;
+use crate::automata::GridSize;
+use crate::render::Renderer;
+
+/// Renders cells to a macroquad texture that is stretched over `dest_size`.
+pub struct AutomataTexture {
+    image: Image,
+    texture: Texture2D,
+    params: DrawTextureParams,
+}
+
+impl AutomataTexture {
+    pub fn new(grid_size: GridSize, dest_size: (f32, f32)) -> AutomataTexture {
+        let GridSize { cols, rows } = grid_size;
+        let image = Image::gen_image_color(cols as u16, rows as u16, BLACK);
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+
+        AutomataTexture {
+            image,
+            texture,
+            params: DrawTextureParams {
+                dest_size: Some(dest_size.into()),
+                ..DrawTextureParams::default()
+            },
+        }
+    }
+}
+
+impl Renderer for AutomataTexture {
+    fn set_pixel(&mut self, col: usize, row: usize, color: crate::color::Color) {
+        self.image.set_pixel(col as u32, row as u32, color.into());
+    }
+
+    fn draw(&mut self) {
+        self.texture.update(&self.image);
+        draw_texture_ex(
+            &self.texture,
+            0.0,
+            0.0,
+            WHITE,
+            self.params.clone()
+        );
+    }
+}